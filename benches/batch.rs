@@ -0,0 +1,67 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::prelude::*;
+
+use line_of_sight::batch::generate_batch_line_of_sight;
+use line_of_sight::sight::generate_line_of_sight;
+use line_of_sight::space::{Point, Segment, Triangle, ALL_LAYERS};
+
+const WIDTH: f32 = 700.0;
+const HEIGHT: f32 = 500.0;
+
+//A walled off room full of random walls, along with viewers looking in random directions
+fn scene(walls: usize, viewers: usize) -> (Vec<Segment>, Vec<(Point, Point, Point)>)
+{
+
+    let mut rng = StdRng::seed_from_u64(41);
+
+    let mut segments: Vec<Segment> = Vec::new();
+    segments.push(Segment::new(Point { x: 0.0, y: 0.0 }, Point { x: WIDTH, y: 0.0 }));
+    segments.push(Segment::new(Point { x: WIDTH, y: 0.0 }, Point { x: WIDTH, y: HEIGHT }));
+    segments.push(Segment::new(Point { x: WIDTH, y: HEIGHT }, Point { x: 0.0, y: HEIGHT }));
+    segments.push(Segment::new(Point { x: 0.0, y: HEIGHT }, Point { x: 0.0, y: 0.0 }));
+
+    for _ in 0..walls
+    {
+
+        let start = Point { x: rng.gen_range(10.0..WIDTH - 10.0), y: rng.gen_range(10.0..HEIGHT - 10.0) };
+        let end = start + Point { x: rng.gen_range(-40.0..40.0), y: rng.gen_range(-40.0..40.0) };
+
+        segments.push(Segment::new(start, end));
+
+    }
+
+    let mut cones: Vec<(Point, Point, Point)> = Vec::new();
+    for _ in 0..viewers
+    {
+
+        let location = Point { x: rng.gen_range(10.0..WIDTH - 10.0), y: rng.gen_range(10.0..HEIGHT - 10.0) };
+        let facing: f32 = rng.gen_range(0.0..std::f32::consts::PI * 2.0);
+
+        cones.push((location, Point { x: (facing + 1.0).cos(), y: (facing + 1.0).sin() }, Point { x: (facing - 1.0).cos(), y: (facing - 1.0).sin() }));
+
+    }
+
+    return (segments, cones);
+
+}
+
+fn serial(segments: &[Segment], viewers: &[(Point, Point, Point)]) -> Vec<Vec<Triangle>>
+{
+
+    return viewers.iter().map(|&(location, upper, lower)| generate_line_of_sight(location, upper, lower, segments, ALL_LAYERS)).collect();
+
+}
+
+fn batch_bench(c: &mut Criterion)
+{
+
+    let (segments, viewers) = scene(200, 256);
+
+    c.bench_function("serial", |b| b.iter(|| serial(black_box(&segments), black_box(&viewers))));
+
+    c.bench_function("rayon", |b| b.iter(|| generate_batch_line_of_sight(black_box(&viewers), black_box(&segments), ALL_LAYERS)));
+
+}
+
+criterion_group!(benches, batch_bench);
+criterion_main!(benches);
@@ -0,0 +1,50 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::prelude::*;
+
+use line_of_sight::demo_walls;
+use line_of_sight::sight::generate_team_line_of_sight;
+use line_of_sight::space::{Point, ALL_LAYERS};
+use line_of_sight::{WORLD_HEIGHT, WORLD_WIDTH};
+
+//Viewers scattered over the demo world looking in random directions
+fn viewers(count: usize) -> Vec<(Point, Point, Point)>
+{
+
+    let mut rng = StdRng::seed_from_u64(30);
+
+    return (0..count).map(|_|
+    {
+
+        let location = Point { x: rng.gen_range(10.0..WORLD_WIDTH), y: rng.gen_range(10.0..WORLD_HEIGHT) };
+        let facing: f32 = rng.gen_range(0.0..std::f32::consts::PI * 2.0);
+
+        return (location, Point { x: (facing + 1.0).cos(), y: (facing + 1.0).sin() }, Point { x: (facing - 1.0).cos(), y: (facing - 1.0).sin() });
+
+    }).collect();
+
+}
+
+//Team sight should grow with the size of the merged region, not with the square of the number of triangles
+fn team_bench(c: &mut Criterion)
+{
+
+    let walls = demo_walls();
+
+    let mut group = c.benchmark_group("team");
+    group.sample_size(10);
+
+    for count in [25, 50, 100, 200].iter()
+    {
+
+        let viewers = viewers(*count);
+
+        group.bench_with_input(BenchmarkId::from_parameter(count), &viewers, |b, viewers| b.iter(|| generate_team_line_of_sight(black_box(viewers), &walls, ALL_LAYERS)));
+
+    }
+
+    group.finish();
+
+}
+
+criterion_group!(benches, team_bench);
+criterion_main!(benches);
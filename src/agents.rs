@@ -0,0 +1,387 @@
+use super::sight::is_visible;
+use super::space::{OccluderSource, Point};
+
+//Something which can both see and be seen
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Agent
+{
+
+	pub location: Point,
+	pub facing: Option<(Point, Point)> //The (upper, lower) cone the agent can see, narrower than pi, or None to see all around
+
+}
+
+impl Agent
+{
+
+	//Returns true if target lies inside the agent's cone, ignoring anything in the way
+	pub fn faces(&self, target: Point) -> bool
+	{
+
+		return match self.facing
+		{
+
+			Some((upper, lower)) => (target - self.location).ray_between(lower, upper),
+			None => true
+
+		};
+
+	}
+
+}
+
+//Which viewers can see which targets, stored row major so that entry (viewer, target) is at viewer * targets + target
+//Viewers and targets are the same agents when the matrix comes from generate_visibility_matrix
+pub struct VisibilityMatrix
+{
+
+	viewers: usize,
+	targets: usize,
+	visible: Vec<bool>
+
+}
+
+impl VisibilityMatrix
+{
+
+	pub fn viewers(&self) -> usize
+	{
+
+		return self.viewers;
+
+	}
+
+	pub fn targets(&self) -> usize
+	{
+
+		return self.targets;
+
+	}
+
+	pub fn sees(&self, viewer: usize, target: usize) -> bool
+	{
+
+		return self.visible[viewer * self.targets + target];
+
+	}
+
+	//The targets viewer can see, in increasing order
+	pub fn visible_from(&self, viewer: usize) -> Vec<usize>
+	{
+
+		return (0..self.targets).filter(|&target| self.sees(viewer, target)).collect();
+
+	}
+
+	//The viewers which can see target, in increasing order
+	pub fn seen_by(&self, target: usize) -> Vec<usize>
+	{
+
+		return (0..self.viewers).filter(|&viewer| self.sees(viewer, target)).collect();
+
+	}
+
+	pub fn visible(&self) -> &[bool]
+	{
+
+		return &self.visible;
+
+	}
+
+}
+
+//Works out which agents can see each other through the occluders on the layers selected by mask. Agents never see themselves
+//Occlusion is symmetric, so each pair is raycast at most once, and not at all if neither agent faces the other
+pub fn generate_visibility_matrix<'a, I>(agents: &[Agent], occluders: I, mask: u32) -> VisibilityMatrix where I: OccluderSource<'a>
+{
+
+	let occluders = occluders.into_occluders();
+	let count = agents.len();
+	let mut visible = vec![false; count * count];
+
+	for i in 0..count
+	{
+
+		for j in i + 1..count
+		{
+
+			let forward = agents[i].faces(agents[j].location);
+			let backward = agents[j].faces(agents[i].location);
+
+			if !forward && !backward
+			{
+
+				continue;
+
+			}
+
+			if is_visible(agents[i].location, agents[j].location, occluders.clone(), mask)
+			{
+
+				visible[i * count + j] = forward;
+				visible[j * count + i] = backward;
+
+			}
+
+		}
+
+	}
+
+	return VisibilityMatrix { viewers: count, targets: count, visible };
+
+}
+
+//Works out which of targets each of viewers can see, e.g. which guards can see the player, without raycasting between the viewers
+//themselves. Only the viewers' facing cones matter, and an agent in both lists sees itself
+pub fn generate_visibility_between<'a, I>(viewers: &[Agent], targets: &[Agent], occluders: I, mask: u32) -> VisibilityMatrix where I: OccluderSource<'a>
+{
+
+	let occluders = occluders.into_occluders();
+	let mut visible = vec![false; viewers.len() * targets.len()];
+
+	for (i, viewer) in viewers.iter().enumerate()
+	{
+
+		for (j, target) in targets.iter().enumerate()
+		{
+
+			visible[i * targets.len() + j] = viewer.faces(target.location) && is_visible(viewer.location, target.location, occluders.clone(), mask);
+
+		}
+
+	}
+
+	return VisibilityMatrix { viewers: viewers.len(), targets: targets.len(), visible };
+
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VisibilityEvent
+{
+
+	Entered { viewer: usize, target: usize },
+	Left { viewer: usize, target: usize }
+
+}
+
+//Turns a visibility matrix computed every tick into events for when targets come into and go out of each viewer's view
+//A target which goes out of view is only reported as having left once it has stayed hidden for grace, so that brief flickers,
+//such as passing behind a pillar, are ignored. If it comes back into view first, nothing is reported at all
+pub struct VisibilityTracker
+{
+
+	viewers: usize,
+	targets: usize,
+	pub grace: f32,
+	seen: Vec<bool>,
+	hidden_for: Vec<f32>
+
+}
+
+impl VisibilityTracker
+{
+
+	pub fn new(viewers: usize, targets: usize, grace: f32) -> VisibilityTracker
+	{
+
+		if grace < 0.0
+		{
+
+			panic!("Cannot track visibility with a negative grace time.");
+
+		}
+
+		return VisibilityTracker { viewers, targets, grace, seen: vec![false; viewers * targets], hidden_for: vec![0.0; viewers * targets] };
+
+	}
+
+	pub fn viewers(&self) -> usize
+	{
+
+		return self.viewers;
+
+	}
+
+	pub fn targets(&self) -> usize
+	{
+
+		return self.targets;
+
+	}
+
+	//Returns true if viewer has seen target and it has not yet left
+	pub fn is_seen(&self, viewer: usize, target: usize) -> bool
+	{
+
+		return self.seen[viewer * self.targets + target];
+
+	}
+
+	//Advances the tracker by elapsed time to this tick's matrix, returning the events in row major order of (viewer, target)
+	pub fn update(&mut self, matrix: &VisibilityMatrix, elapsed: f32) -> Vec<VisibilityEvent>
+	{
+
+		if matrix.viewers() != self.viewers || matrix.targets() != self.targets
+		{
+
+			panic!("Cannot update visibility tracker with a matrix for a different number of viewers or targets.");
+
+		}
+
+		let mut events: Vec<VisibilityEvent> = Vec::new();
+
+		for viewer in 0..self.viewers
+		{
+
+			for target in 0..self.targets
+			{
+
+				let index = viewer * self.targets + target;
+
+				if matrix.sees(viewer, target)
+				{
+
+					self.hidden_for[index] = 0.0;
+
+					if !self.seen[index]
+					{
+
+						self.seen[index] = true;
+						events.push(VisibilityEvent::Entered { viewer, target });
+
+					}
+
+				}
+				else if self.seen[index]
+				{
+
+					self.hidden_for[index] += elapsed;
+
+					if self.hidden_for[index] >= self.grace
+					{
+
+						self.seen[index] = false;
+						self.hidden_for[index] = 0.0;
+						events.push(VisibilityEvent::Left { viewer, target });
+
+					}
+
+				}
+
+			}
+
+		}
+
+		return events;
+
+	}
+
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::space::{Segment, ALL_LAYERS};
+
+	#[test]
+	fn cones_and_walls()
+	{
+
+		let walls = vec![Segment::new(Point { x: 5.0, y: -1.0 }, Point { x: 5.0, y: 1.0 })];
+
+		let agents = vec![
+			Agent { location: Point { x: 0.0, y: 0.0 }, facing: None },
+			Agent { location: Point { x: 10.0, y: 0.0 }, facing: None },
+			Agent { location: Point { x: 0.0, y: 5.0 }, facing: Some((Point { x: -1.0, y: -1.0 }, Point { x: -1.0, y: 1.0 })) }, //Looking away from everyone
+			Agent { location: Point { x: 10.0, y: 5.0 }, facing: Some((Point { x: -1.0, y: -1.0 }, Point { x: -1.0, y: 1.0 })) }
+		];
+
+		let matrix = generate_visibility_matrix(&agents, &walls, ALL_LAYERS);
+
+		//The wall hides the first two agents from each other
+		assert!(!matrix.sees(0, 1));
+		assert!(!matrix.sees(1, 0));
+
+		//Facing only matters to the one doing the looking
+		assert!(matrix.sees(0, 2));
+		assert!(!matrix.sees(2, 0));
+		assert!(!matrix.sees(2, 3));
+		assert!(matrix.sees(3, 2));
+
+		assert_eq!(vec![2, 3], matrix.visible_from(0));
+		assert_eq!(vec![0, 1, 3], matrix.seen_by(2));
+		assert!((0..agents.len()).all(|i| !matrix.sees(i, i)));
+		assert_eq!((agents.len(), agents.len()), (matrix.viewers(), matrix.targets()));
+
+	}
+
+	#[test]
+	fn tracker_grace()
+	{
+
+		let walls = vec![Segment::new(Point { x: 5.0, y: -1.0 }, Point { x: 5.0, y: 1.0 })];
+		let mut agents = vec![Agent { location: Point { x: 0.0, y: 0.0 }, facing: None }, Agent { location: Point { x: 10.0, y: 3.0 }, facing: None }];
+
+		let mut tracker = VisibilityTracker::new(2, 2, 0.5);
+
+		let events = tracker.update(&generate_visibility_matrix(&agents, &walls, ALL_LAYERS), 0.25);
+		assert_eq!(vec![VisibilityEvent::Entered { viewer: 0, target: 1 }, VisibilityEvent::Entered { viewer: 1, target: 0 }], events);
+
+		//Ducking behind the wall briefly goes unreported
+		agents[1].location = Point { x: 10.0, y: 0.0 };
+		assert!(tracker.update(&generate_visibility_matrix(&agents, &walls, ALL_LAYERS), 0.25).is_empty());
+		assert!(tracker.is_seen(0, 1));
+
+		agents[1].location = Point { x: 10.0, y: 3.0 };
+		assert!(tracker.update(&generate_visibility_matrix(&agents, &walls, ALL_LAYERS), 0.25).is_empty());
+
+		//Staying hidden for the whole grace time counts as leaving
+		agents[1].location = Point { x: 10.0, y: 0.0 };
+		assert!(tracker.update(&generate_visibility_matrix(&agents, &walls, ALL_LAYERS), 0.25).is_empty());
+		let events = tracker.update(&generate_visibility_matrix(&agents, &walls, ALL_LAYERS), 0.25);
+		assert_eq!(vec![VisibilityEvent::Left { viewer: 0, target: 1 }, VisibilityEvent::Left { viewer: 1, target: 0 }], events);
+		assert!(!tracker.is_seen(0, 1));
+
+		//Without any grace, leaving is reported straight away
+		let mut immediate = VisibilityTracker::new(2, 2, 0.0);
+		agents[1].location = Point { x: 10.0, y: 3.0 };
+		immediate.update(&generate_visibility_matrix(&agents, &walls, ALL_LAYERS), 0.25);
+		agents[1].location = Point { x: 10.0, y: 0.0 };
+		assert_eq!(2, immediate.update(&generate_visibility_matrix(&agents, &walls, ALL_LAYERS), 0.25).len());
+
+	}
+
+	#[test]
+	fn guards_and_player()
+	{
+
+		let walls = vec![Segment::new(Point { x: 5.0, y: -1.0 }, Point { x: 5.0, y: 1.0 })];
+
+		let guards = vec![
+			Agent { location: Point { x: 0.0, y: 0.0 }, facing: None },
+			Agent { location: Point { x: 10.0, y: 5.0 }, facing: Some((Point { x: 1.0, y: 1.0 }, Point { x: 1.0, y: -1.0 })) } //Looking away from the player
+		];
+		let mut player = vec![Agent { location: Point { x: 10.0, y: 0.0 }, facing: None }];
+
+		let matrix = generate_visibility_between(&guards, &player, &walls, ALL_LAYERS);
+		assert_eq!((2, 1), (matrix.viewers(), matrix.targets()));
+		assert!(!matrix.sees(0, 0) && !matrix.sees(1, 0));
+		assert!(matrix.seen_by(0).is_empty());
+
+		let mut tracker = VisibilityTracker::new(guards.len(), player.len(), 0.0);
+		assert!(tracker.update(&matrix, 0.25).is_empty());
+
+		//Stepping out from behind the wall is spotted by the first guard only
+		player[0].location = Point { x: 10.0, y: 3.0 };
+		let events = tracker.update(&generate_visibility_between(&guards, &player, &walls, ALL_LAYERS), 0.25);
+		assert_eq!(vec![VisibilityEvent::Entered { viewer: 0, target: 0 }], events);
+
+		player[0].location = Point { x: 10.0, y: 0.0 };
+		let events = tracker.update(&generate_visibility_between(&guards, &player, &walls, ALL_LAYERS), 0.25);
+		assert_eq!(vec![VisibilityEvent::Left { viewer: 0, target: 0 }], events);
+
+	}
+
+}
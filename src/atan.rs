@@ -0,0 +1,129 @@
+use super::sight::VisibilityAlgorithm;
+use super::space::{normalize_angle, Occluder, OccluderSource, Point, Triangle, FLOATING_POINT_ERROR};
+
+//Computes line of sight the traditional way, measuring the angle of every ray with atan2
+//Slower than the reference algorithm, but kept around to check it against and to compare timings
+pub struct AtanContext
+{
+
+	rays: Vec<(f32, Point)> //Each ray along with its angle counterclockwise from lower
+
+}
+
+impl AtanContext
+{
+
+	pub fn new() -> AtanContext
+	{
+
+		return AtanContext { rays: Vec::new() };
+
+	}
+
+}
+
+impl VisibilityAlgorithm for AtanContext
+{
+
+	fn compute_into<'a, I>(&mut self, location: Point, upper: Point, lower: Point, occluders: I, mask: u32, output: &mut Vec<Triangle>) where I: OccluderSource<'a>
+	{
+
+		let occluders = occluders.into_occluders();
+
+		let lower_angle = normalize_angle(lower.y.atan2(lower.x));
+		let upper_angle = normalize_angle(upper.y.atan2(upper.x));
+		let offset = |ray: Point| -> f32 { normalize_angle(ray.y.atan2(ray.x) - lower_angle) };
+
+		let rays = &mut self.rays;
+		rays.clear();
+		rays.push((0.0, lower));
+		rays.push((offset(upper), upper));
+
+		for occluder in occluders.clone()
+		{
+
+			if !occluder.occludes(mask)
+			{
+
+				continue;
+
+			}
+
+			occluder.silhouette(location, &mut |ray|
+			{
+
+				if ray.ray_between_atan(lower_angle, upper_angle)
+				{
+
+					rays.push((offset(ray), ray));
+
+				}
+
+			});
+
+		}
+
+		rays.sort_unstable_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+		output.clear();
+
+		for i in 0..rays.len() - 1
+		{
+
+			let current = rays[i].1;
+			let next = rays[i + 1].1;
+
+			let mut shortest: Option<(f32, f32, usize)> = None;
+
+			for (index, occluder) in occluders.clone().enumerate()
+			{
+
+				if !occluder.occludes(mask)
+				{
+
+					continue;
+
+				}
+
+				if let (Some(cast_current), Some(cast_next)) = (occluder.raycast(location, current), occluder.raycast(location, next))
+				{
+
+					//Walls meeting at the point the current ray passes through are tied, so the one closer along the next ray is in front
+					if shortest.is_none_or(|(closest, closest_next, _)| if (cast_current - closest).abs() < FLOATING_POINT_ERROR { cast_next < closest_next } else { cast_current < closest })
+					{
+
+						shortest = Some((cast_current, cast_next, index));
+
+					}
+
+				}
+
+			}
+
+			let triangle = match shortest
+			{
+
+				Some((cast_current, cast_next, index)) => Triangle::with_wall(location, location + current.scale(cast_current), location + next.scale(cast_next), Some(index)),
+				None => Triangle::new(location, location, location)
+
+			};
+
+			output.push(triangle);
+
+		}
+
+	}
+
+}
+
+impl Default for AtanContext
+{
+
+	fn default() -> AtanContext
+	{
+
+		return AtanContext::new();
+
+	}
+
+}
@@ -0,0 +1,90 @@
+use super::sight::VisibilityContext;
+use super::space::{Occluder, Point, Triangle};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+//Computes the line of sight of every (location, upper, lower) viewer, returned in the same order as viewers
+//With the rayon feature enabled the viewers are split across threads, each reusing its own VisibilityContext
+#[cfg(feature = "rayon")]
+pub fn generate_batch_line_of_sight<O>(viewers: &[(Point, Point, Point)], occluders: &[O], mask: u32) -> Vec<Vec<Triangle>> where O: Occluder + Sync
+{
+
+	return viewers.par_iter().map_init(VisibilityContext::new, |context, &(location, upper, lower)|
+	{
+
+		let mut line_of_sight: Vec<Triangle> = Vec::new();
+		context.compute_into(location, upper, lower, occluders, mask, &mut line_of_sight);
+
+		return line_of_sight;
+
+	}).collect();
+
+}
+
+//Computes the line of sight of every (location, upper, lower) viewer, returned in the same order as viewers
+//Without the rayon feature the viewers are handled one after another, sharing a single VisibilityContext
+#[cfg(not(feature = "rayon"))]
+pub fn generate_batch_line_of_sight<O>(viewers: &[(Point, Point, Point)], occluders: &[O], mask: u32) -> Vec<Vec<Triangle>> where O: Occluder + Sync
+{
+
+	let mut context = VisibilityContext::new();
+
+	return viewers.iter().map(|&(location, upper, lower)|
+	{
+
+		let mut line_of_sight: Vec<Triangle> = Vec::new();
+		context.compute_into(location, upper, lower, occluders, mask, &mut line_of_sight);
+
+		return line_of_sight;
+
+	}).collect();
+
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::demo_walls;
+	use crate::sight::generate_line_of_sight;
+	use crate::space::ALL_LAYERS;
+
+	#[test]
+	fn matches_serial()
+	{
+
+		let walls = demo_walls();
+
+		let mut viewers = Vec::new();
+		for i in 0..40
+		{
+
+			let location = Point { x: 20.0 + 16.0 * i as f32, y: 30.0 + 11.0 * i as f32 };
+			viewers.push((location, Point { x: -1.0, y: 1.0 + 0.1 * i as f32 }, Point { x: 1.0, y: -0.5 }));
+
+		}
+
+		let batch = generate_batch_line_of_sight(&viewers, &walls, ALL_LAYERS);
+
+		assert_eq!(viewers.len(), batch.len());
+		for (&(location, upper, lower), line_of_sight) in viewers.iter().zip(batch.iter())
+		{
+
+			let expected = generate_line_of_sight(location, upper, lower, &walls, ALL_LAYERS);
+
+			assert_eq!(expected.len(), line_of_sight.len());
+			for (a, b) in expected.iter().zip(line_of_sight.iter())
+			{
+
+				assert_eq!(a.vertices, b.vertices);
+				assert_eq!(a.wall, b.wall);
+
+			}
+
+		}
+
+	}
+
+}
@@ -0,0 +1,336 @@
+use super::space::{Point, Segment};
+
+//A segment which may have collapsed to a point while being cleaned up
+#[derive(Clone, Copy)]
+struct Piece
+{
+
+	start: Point,
+	end: Point,
+	layers: u32,
+	enabled: bool
+
+}
+
+fn distance_squared(a: Point, b: Point) -> f32
+{
+
+	let difference = b - a;
+
+	return difference.dot(&difference);
+
+}
+
+fn cross(a: Point, b: Point) -> f32
+{
+
+	return a.x * b.y - a.y * b.x;
+
+}
+
+//Snaps every endpoint to the first endpoint seen within tolerance of it
+fn weld(pieces: &mut Vec<Piece>, tolerance: f32)
+{
+
+	let mut welded: Vec<Point> = Vec::new();
+
+	let mut snap = |point: Point| -> Point
+	{
+
+		for existing in welded.iter()
+		{
+
+			if distance_squared(*existing, point) <= tolerance * tolerance
+			{
+
+				return *existing;
+
+			}
+
+		}
+
+		welded.push(point);
+
+		return point;
+
+	};
+
+	for piece in pieces.iter_mut()
+	{
+
+		piece.start = snap(piece.start);
+		piece.end = snap(piece.end);
+
+	}
+
+	pieces.retain(|piece| distance_squared(piece.start, piece.end) > tolerance * tolerance);
+
+}
+
+//Joins collinear pieces with the same layers and enabled state whose overlap is longer than tolerance, until no more can be joined
+fn merge_collinear(pieces: &mut Vec<Piece>, tolerance: f32)
+{
+
+	let mut i = 0;
+	while i < pieces.len()
+	{
+
+		let mut merged = false;
+
+		for j in i + 1..pieces.len()
+		{
+
+			let a = pieces[i];
+			let b = pieces[j];
+
+			if a.layers != b.layers || a.enabled != b.enabled
+			{
+
+				continue;
+
+			}
+
+			let direction = a.end - a.start;
+			let length = direction.dot(&direction).sqrt();
+
+			//Both endpoints of b must lie on the line through a
+			if cross(direction, b.start - a.start).abs() > tolerance * length || cross(direction, b.end - a.start).abs() > tolerance * length
+			{
+
+				continue;
+
+			}
+
+			//Measure everything as distances along a
+			let b_start = direction.dot(&(b.start - a.start)) / length;
+			let b_end = direction.dot(&(b.end - a.start)) / length;
+
+			if b_start.max(b_end).min(length) - b_start.min(b_end).max(0.0) <= tolerance
+			{
+
+				continue;
+
+			}
+
+			let mut extremes = [(0.0, a.start), (length, a.end), (b_start, b.start), (b_end, b.end)];
+			extremes.sort_unstable_by(|first, second| first.0.partial_cmp(&second.0).unwrap());
+
+			pieces[i] = Piece { start: extremes[0].1, end: extremes[3].1, layers: a.layers, enabled: a.enabled };
+			pieces.swap_remove(j);
+
+			merged = true;
+			break;
+
+		}
+
+		//A merged piece may now overlap pieces it was already compared against
+		if merged
+		{
+
+			i = 0;
+
+		}
+		else
+		{
+
+			i += 1;
+
+		}
+
+	}
+
+}
+
+//Splits pieces wherever another piece crosses them or ends on them
+fn split_at_intersections(pieces: &[Piece], tolerance: f32) -> Vec<Piece>
+{
+
+	let mut splits: Vec<Vec<Point>> = vec![Vec::new(); pieces.len()];
+
+	for i in 0..pieces.len()
+	{
+
+		for j in i + 1..pieces.len()
+		{
+
+			let a = pieces[i];
+			let b = pieces[j];
+
+			let a_direction = a.end - a.start;
+			let b_direction = b.end - b.start;
+
+			let denominator = cross(a_direction, b_direction);
+			if denominator.abs() <= f32::EPSILON * a_direction.dot(&a_direction).max(b_direction.dot(&b_direction)) //Parallel pieces never cross
+			{
+
+				continue;
+
+			}
+
+			let offset = b.start - a.start;
+			let s = cross(offset, b_direction) / denominator;
+			let t = cross(offset, a_direction) / denominator;
+
+			let a_tolerance = tolerance / a_direction.dot(&a_direction).sqrt();
+			let b_tolerance = tolerance / b_direction.dot(&b_direction).sqrt();
+
+			if s < -a_tolerance || s > 1.0 + a_tolerance || t < -b_tolerance || t > 1.0 + b_tolerance
+			{
+
+				continue;
+
+			}
+
+			//Prefer an existing endpoint over the computed intersection so that welded endpoints stay shared
+			let mut point = a.start + a_direction.scale(s);
+			for endpoint in [a.start, a.end, b.start, b.end].iter()
+			{
+
+				if distance_squared(*endpoint, point) <= tolerance * tolerance
+				{
+
+					point = *endpoint;
+					break;
+
+				}
+
+			}
+
+			if distance_squared(point, a.start) > tolerance * tolerance && distance_squared(point, a.end) > tolerance * tolerance
+			{
+
+				splits[i].push(point);
+
+			}
+
+			if distance_squared(point, b.start) > tolerance * tolerance && distance_squared(point, b.end) > tolerance * tolerance
+			{
+
+				splits[j].push(point);
+
+			}
+
+		}
+
+	}
+
+	let mut split: Vec<Piece> = Vec::new();
+
+	for (piece, points) in pieces.iter().zip(splits.iter_mut())
+	{
+
+		let direction = piece.end - piece.start;
+		points.sort_unstable_by(|a, b| direction.dot(&(*a - piece.start)).partial_cmp(&direction.dot(&(*b - piece.start))).unwrap());
+		points.push(piece.end);
+
+		let mut start = piece.start;
+		for point in points.iter()
+		{
+
+			if distance_squared(start, *point) > tolerance * tolerance
+			{
+
+				split.push(Piece { start, end: *point, layers: piece.layers, enabled: piece.enabled });
+				start = *point;
+
+			}
+
+		}
+
+	}
+
+	return split;
+
+}
+
+//Cleans up imported level geometry so that it does not leak line of sight. Endpoints within tolerance of each other are welded,
+//overlapping collinear segments with the same layers and enabled state are joined, segments are split where they cross or
+//meet another segment, and anything shorter than tolerance is dropped
+pub fn clean_segments<'a, I>(segments: I, tolerance: f32) -> Vec<Segment> where I: IntoIterator<Item = &'a Segment>
+{
+
+	let mut pieces: Vec<Piece> = segments.into_iter().map(|segment| Piece { start: segment.start, end: segment.end, layers: segment.layers, enabled: segment.enabled }).collect();
+
+	weld(&mut pieces, tolerance);
+	merge_collinear(&mut pieces, tolerance);
+
+	return split_at_intersections(&pieces, tolerance).into_iter().map(|piece|
+	{
+
+		let mut segment = Segment::with_layers(piece.start, piece.end, piece.layers);
+		segment.enabled = piece.enabled;
+
+		return segment;
+
+	}).collect();
+
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	fn point(x: f32, y: f32) -> Point
+	{
+
+		return Point { x, y };
+
+	}
+
+	#[test]
+	fn weld_and_drop()
+	{
+
+		let mut segments = Vec::new();
+		segments.push(Segment::new(point(0.0, 0.0), point(1.0, 0.0)));
+		segments.push(Segment::new(point(1.001, 0.002), point(1.0, 1.0)));
+		segments.push(Segment::new(point(5.0, 5.0), point(5.005, 5.0)));
+
+		let cleaned = clean_segments(&segments, 0.01);
+
+		assert_eq!(2, cleaned.len());
+		assert_eq!(cleaned[0].end, cleaned[1].start);
+
+	}
+
+	#[test]
+	fn merge_overlaps()
+	{
+
+		let mut segments = Vec::new();
+		segments.push(Segment::new(point(0.0, 0.0), point(2.0, 0.0)));
+		segments.push(Segment::new(point(3.0, 0.0), point(1.0, 0.0)));
+		segments.push(Segment::new(point(2.5, 0.0), point(4.0, 0.0)));
+		segments.push(Segment::with_layers(point(0.0, 0.0), point(4.0, 0.0), 0b10));
+
+		let cleaned = clean_segments(&segments, 0.01);
+
+		assert_eq!(2, cleaned.len());
+		assert!(cleaned.contains(&Segment::new(point(0.0, 0.0), point(4.0, 0.0))));
+		assert!(cleaned.contains(&Segment::with_layers(point(0.0, 0.0), point(4.0, 0.0), 0b10)));
+
+	}
+
+	#[test]
+	fn split_crossings()
+	{
+
+		let mut segments = Vec::new();
+		segments.push(Segment::new(point(0.0, 0.0), point(2.0, 2.0)));
+		segments.push(Segment::new(point(0.0, 2.0), point(2.0, 0.0)));
+		segments.push(Segment::new(point(0.0, -1.0), point(4.0, -1.0)));
+		segments.push(Segment::new(point(3.0, -1.0), point(3.0, -3.0)));
+
+		let cleaned = clean_segments(&segments, 0.01);
+
+		assert_eq!(7, cleaned.len());
+		assert!(cleaned.contains(&Segment::new(point(0.0, 0.0), point(1.0, 1.0))));
+		assert!(cleaned.contains(&Segment::new(point(1.0, 1.0), point(2.0, 0.0))));
+		assert!(cleaned.contains(&Segment::new(point(0.0, -1.0), point(3.0, -1.0))));
+		assert!(cleaned.contains(&Segment::new(point(3.0, -1.0), point(4.0, -1.0))));
+
+	}
+
+}
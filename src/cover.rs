@@ -0,0 +1,115 @@
+use super::agents::Agent;
+use super::exposure::CIRCLE_SIDES;
+use super::sight::{generate_full_line_of_sight, generate_line_of_sight};
+use super::space::{OccluderSource, Point, Polygon, Rectangle, Triangle};
+
+//Computes the line of sight of every threat, looking all around for threats without a facing cone
+//The occluders are closed off by bounds, which must contain every threat, so that open ground is covered by the sight rather than
+//left out of it where rays escape. The result can be kept and passed to find_cover as long as neither the threats nor the occluders move
+pub fn generate_threat_line_of_sight<'a, I>(threats: &[Agent], occluders: I, mask: u32, bounds: &Rectangle) -> Vec<Vec<Triangle>> where I: OccluderSource<'a>
+{
+
+	let occluders = bounds.close_off(occluders);
+
+	return threats.iter().map(|threat| match threat.facing
+	{
+
+		Some((upper, lower)) => generate_line_of_sight(threat.location, upper, lower, &occluders, mask),
+		None => generate_full_line_of_sight(threat.location, &occluders, mask)
+
+	}).collect();
+
+}
+
+//Returns the points of a grid with the given spacing within radius of location which none of the threats can see, closest first
+//Each entry of threat_sight is the line of sight of one threat, from generate_threat_line_of_sight with the same bounds
+//Points outside bounds are left out, as the threats' sight says nothing about them
+pub fn find_cover(location: Point, radius: f32, spacing: f32, threat_sight: &[Vec<Triangle>], bounds: &Rectangle) -> Vec<Point>
+{
+
+	let mut cover: Vec<Point> = Polygon::circle(location, radius, CIRCLE_SIDES).grid(spacing).into_iter().filter(|point|
+	{
+
+		return bounds.contains(*point) && !threat_sight.iter().any(|sight| sight.iter().any(|triangle| triangle.contains(*point)));
+
+	}).collect();
+
+	let distance = |point: &Point| (*point - location).dot(&(*point - location));
+	cover.sort_by(|a, b| distance(a).partial_cmp(&distance(b)).unwrap());
+
+	return cover;
+
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::sight::is_visible;
+	use crate::space::{Segment, ALL_LAYERS};
+
+	#[test]
+	fn behind_pillar()
+	{
+
+		let mut walls = Vec::new();
+		walls.push(Segment::new(Point { x: -10.0, y: -10.0 }, Point { x: 10.0, y: -10.0 }));
+		walls.push(Segment::new(Point { x: 10.0, y: -10.0 }, Point { x: 10.0, y: 10.0 }));
+		walls.push(Segment::new(Point { x: 10.0, y: 10.0 }, Point { x: -10.0, y: 10.0 }));
+		walls.push(Segment::new(Point { x: -10.0, y: 10.0 }, Point { x: -10.0, y: -10.0 }));
+		walls.push(Segment::new(Point { x: 5.0, y: -1.0 }, Point { x: 5.0, y: 1.0 }));
+
+		let bounds = Rectangle::new(Point { x: -10.0, y: -10.0 }, Point { x: 10.0, y: 10.0 });
+
+		let threats = vec![Agent { location: Point { x: 0.0, y: 0.0 }, facing: None }];
+		let sight = generate_threat_line_of_sight(&threats, &walls, ALL_LAYERS, &bounds);
+
+		let location = Point { x: 6.0, y: 3.0 };
+		let cover = find_cover(location, 3.0, 0.5, &sight, &bounds);
+
+		assert!(!cover.is_empty());
+		assert!(cover.iter().all(|point| !is_visible(threats[0].location, *point, &walls, ALL_LAYERS)));
+		assert!(cover.windows(2).all(|pair| (pair[0] - location).dot(&(pair[0] - location)) <= (pair[1] - location).dot(&(pair[1] - location))));
+
+		//The closest cover is just behind the top of the pillar
+		assert!(cover[0].x > 5.0 && cover[0].y < 1.5 && cover[0].y > 0.0);
+
+		//A second threat on the far side of the pillar leaves nowhere to hide
+		let threats = vec![threats[0], Agent { location: Point { x: 9.0, y: 0.0 }, facing: None }];
+		assert!(find_cover(location, 3.0, 0.5, &generate_threat_line_of_sight(&threats, &walls, ALL_LAYERS, &bounds), &bounds).is_empty());
+
+		//Unless that threat is looking the other way
+		let threats = vec![threats[0], Agent { location: Point { x: 9.0, y: 0.0 }, facing: Some((Point { x: 1.0, y: 1.0 }, Point { x: 1.0, y: -1.0 })) }];
+		assert_eq!(cover, find_cover(location, 3.0, 0.5, &generate_threat_line_of_sight(&threats, &walls, ALL_LAYERS, &bounds), &bounds));
+
+	}
+
+	#[test]
+	fn open_field()
+	{
+
+		//Nothing but the bounds encloses the field, so the threat's sight only stops at the wall and the bounds
+		let walls = vec![Segment::new(Point { x: 5.0, y: -2.0 }, Point { x: 5.0, y: 2.0 })];
+		let bounds = Rectangle::new(Point { x: -20.0, y: -20.0 }, Point { x: 20.0, y: 20.0 });
+
+		let threats = vec![Agent { location: Point { x: 0.0, y: 0.0 }, facing: None }];
+		let sight = generate_threat_line_of_sight(&threats, &walls, ALL_LAYERS, &bounds);
+
+		let location = Point { x: 7.0, y: 0.0 };
+		let cover = find_cover(location, 1.0, 0.25, &sight, &bounds);
+
+		assert!(!cover.is_empty());
+		assert!(cover.iter().all(|point| !is_visible(threats[0].location, *point, &walls, ALL_LAYERS)));
+		assert!(cover.iter().any(|point| (*point - location).dot(&(*point - location)) < 0.25));
+
+		//Out in the open there is nowhere to hide
+		assert!(find_cover(Point { x: 0.0, y: 10.0 }, 1.0, 0.25, &sight, &bounds).is_empty());
+		assert!(find_cover(Point { x: -7.0, y: 0.0 }, 1.0, 0.25, &sight, &bounds).is_empty());
+
+		//Points beyond the bounds are not offered either, as nothing is known about them
+		assert!(find_cover(Point { x: 7.0, y: 20.0 }, 1.0, 0.25, &sight, &bounds).is_empty());
+
+	}
+
+}
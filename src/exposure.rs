@@ -0,0 +1,104 @@
+use super::region;
+use super::space::{Point, Polygon, Triangle};
+
+//Number of sides used to approximate circular targets
+pub const CIRCLE_SIDES: usize = 32;
+
+//Returns the fraction of target's area inside a line of sight, along with the visible part of target as counterclockwise polygons
+//Target may be any simple polygon, e.g. a hitbox or the outline of a building, and may be wound either way
+pub fn polygon_exposure(target: &Polygon, line_of_sight: &[Triangle]) -> (f32, Vec<Polygon>)
+{
+
+	let area = target.area();
+	if area == 0.0 || target.vertices.len() < 3
+	{
+
+		return (0.0, Vec::new());
+
+	}
+
+	//Split the target into triangles so that it can be intersected with the line of sight like any other region
+	let visible = region::intersection(&[line_of_sight.to_vec(), target.triangulate()]);
+	let visible_area: f32 = visible.iter().map(|polygon| polygon.area()).sum();
+
+	return ((visible_area / area).min(1.0), visible);
+
+}
+
+//Returns the fraction of a circle's area inside a line of sight, along with the visible part of the circle as counterclockwise polygons
+//The circle is approximated by a polygon with CIRCLE_SIDES sides
+pub fn circle_exposure(center: Point, radius: f32, line_of_sight: &[Triangle]) -> (f32, Vec<Polygon>)
+{
+
+	return polygon_exposure(&Polygon::circle(center, radius, CIRCLE_SIDES), line_of_sight);
+
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::sight::generate_line_of_sight;
+	use crate::space::{Segment, ALL_LAYERS, FLOATING_POINT_ERROR};
+
+	#[test]
+	fn half_hidden()
+	{
+
+		//A wall hides the lower half of everything behind it
+		let mut walls = Vec::new();
+		walls.push(Segment::new(Point { x: 2.0, y: -5.0 }, Point { x: 2.0, y: 0.0 }));
+		walls.push(Segment::new(Point { x: 10.0, y: -20.0 }, Point { x: 10.0, y: 20.0 }));
+
+		let sight = generate_line_of_sight(Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 1.0 }, Point { x: 1.0, y: -1.0 }, &walls, ALL_LAYERS);
+
+		let target = Polygon::new(vec![Point { x: 5.0, y: -1.0 }, Point { x: 7.0, y: -1.0 }, Point { x: 7.0, y: 1.0 }, Point { x: 5.0, y: 1.0 }]);
+		let (fraction, visible) = polygon_exposure(&target, &sight);
+
+		assert!((fraction - 0.5).abs() < 0.001);
+		assert!(visible.iter().all(|polygon| polygon.signed_area() > 0.0));
+		assert!(visible.iter().all(|polygon| polygon.vertices.iter().all(|vertex| vertex.y > -FLOATING_POINT_ERROR)));
+
+		let (fraction, _) = circle_exposure(Point { x: 6.0, y: 0.0 }, 1.0, &sight);
+		assert!((fraction - 0.5).abs() < 0.001);
+
+		//Targets entirely in view or entirely out of it
+		let (fraction, visible) = circle_exposure(Point { x: 6.0, y: 3.0 }, 1.0, &sight);
+		assert!((fraction - 1.0).abs() < 0.001);
+		assert_eq!(1, visible.len());
+
+		let (fraction, visible) = circle_exposure(Point { x: 6.0, y: -3.0 }, 1.0, &sight);
+		assert_eq!(0.0, fraction);
+		assert!(visible.is_empty());
+
+	}
+
+	#[test]
+	fn concave_target()
+	{
+
+		let walls = vec![Segment::new(Point { x: 10.0, y: -20.0 }, Point { x: 10.0, y: 20.0 })];
+		let sight = generate_line_of_sight(Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 1.0 }, Point { x: 1.0, y: -1.0 }, &walls, ALL_LAYERS);
+
+		//A U shape opening to the right, wound clockwise, with an area of 7. Fanning it from its first vertex would cover the gap between its arms
+		let target = Polygon::new(vec![
+			Point { x: 5.0, y: -1.5 }, Point { x: 5.0, y: 1.5 }, Point { x: 8.0, y: 1.5 }, Point { x: 8.0, y: 0.5 },
+			Point { x: 6.0, y: 0.5 }, Point { x: 6.0, y: -0.5 }, Point { x: 8.0, y: -0.5 }, Point { x: 8.0, y: -1.5 }
+		]);
+		let (fraction, visible) = polygon_exposure(&target, &sight);
+
+		assert!((fraction - 1.0).abs() < 0.001);
+		assert!((visible.iter().map(|polygon| polygon.area()).sum::<f32>() - 7.0).abs() < 0.01);
+		assert!(visible.iter().all(|polygon| !polygon.contains(Point { x: 7.0, y: 0.0 })));
+
+		//Hiding everything above the middle of the gap leaves the lower half of the base and the lower arm
+		let walls = vec![Segment::new(Point { x: 2.0, y: 0.0 }, Point { x: 2.0, y: 5.0 }), walls[0]];
+		let sight = generate_line_of_sight(Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 1.0 }, Point { x: 1.0, y: -1.0 }, &walls, ALL_LAYERS);
+		let (fraction, _) = polygon_exposure(&target, &sight);
+
+		assert!((fraction - 0.5).abs() < 0.001);
+
+	}
+
+}
@@ -0,0 +1,327 @@
+use super::space::{Point, Rectangle, Triangle, FLOATING_POINT_ERROR};
+
+//How a cell decides whether a triangle covers it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Coverage
+{
+
+	Center, //The center of the cell lies inside the triangle
+	Overlap //Any part of the cell with positive area lies inside the triangle
+
+}
+
+//Returns true unless axis separates the triangle from the rectangle
+fn overlap_along(axis: Point, triangle: &Triangle, corners: &[Point; 4]) -> bool
+{
+
+	let mut triangle_min = f32::INFINITY;
+	let mut triangle_max = f32::NEG_INFINITY;
+
+	for vertex in triangle.vertices.iter()
+	{
+
+		let projection = axis.dot(vertex);
+		triangle_min = triangle_min.min(projection);
+		triangle_max = triangle_max.max(projection);
+
+	}
+
+	let mut cell_min = f32::INFINITY;
+	let mut cell_max = f32::NEG_INFINITY;
+
+	for corner in corners.iter()
+	{
+
+		let projection = axis.dot(corner);
+		cell_min = cell_min.min(projection);
+		cell_max = cell_max.max(projection);
+
+	}
+
+	return triangle_max > cell_min + FLOATING_POINT_ERROR && triangle_min < cell_max - FLOATING_POINT_ERROR;
+
+}
+
+//Separating axis test between a triangle and a rectangle, where merely touching does not count as overlapping
+fn overlaps(triangle: &Triangle, cell: &Rectangle) -> bool
+{
+
+	let corners = cell.corners();
+
+	if !overlap_along(Point { x: 1.0, y: 0.0 }, triangle, &corners) || !overlap_along(Point { x: 0.0, y: 1.0 }, triangle, &corners)
+	{
+
+		return false;
+
+	}
+
+	for i in 0..3
+	{
+
+		let edge = triangle.vertices[(i + 1) % 3] - triangle.vertices[i];
+
+		if !overlap_along(Point { x: -edge.y, y: edge.x }, triangle, &corners)
+		{
+
+			return false;
+
+		}
+
+	}
+
+	return true;
+
+}
+
+//Calls covered with the column and row of every cell of a width by height grid, anchored at origin, which triangle covers
+pub fn for_each_covered_cell<F>(triangle: &Triangle, origin: Point, cell_size: f32, width: usize, height: usize, coverage: Coverage, mut covered: F) where F: FnMut(usize, usize)
+{
+
+	//Triangles between rays with the same direction cover nothing
+	if triangle.area() < FLOATING_POINT_ERROR
+	{
+
+		return;
+
+	}
+
+	let mut min = triangle.vertices[0];
+	let mut max = triangle.vertices[0];
+
+	for vertex in triangle.vertices.iter()
+	{
+
+		min = Point { x: min.x.min(vertex.x), y: min.y.min(vertex.y) };
+		max = Point { x: max.x.max(vertex.x), y: max.y.max(vertex.y) };
+
+	}
+
+	//Only the cells under the triangle's bounding box can be covered
+	let first_column = ((min.x - origin.x) / cell_size).floor().max(0.0) as usize;
+	let first_row = ((min.y - origin.y) / cell_size).floor().max(0.0) as usize;
+	let last_column = ((((max.x - origin.x) / cell_size).floor().max(-1.0) + 1.0) as usize).min(width);
+	let last_row = ((((max.y - origin.y) / cell_size).floor().max(-1.0) + 1.0) as usize).min(height);
+
+	for row in first_row..last_row
+	{
+
+		for column in first_column..last_column
+		{
+
+			let min = Point { x: origin.x + column as f32 * cell_size, y: origin.y + row as f32 * cell_size };
+			let cell = Rectangle::new(min, Point { x: min.x + cell_size, y: min.y + cell_size });
+
+			let hit = match coverage
+			{
+
+				Coverage::Center => triangle.contains(Point { x: min.x + cell_size * 0.5, y: min.y + cell_size * 0.5 }),
+				Coverage::Overlap => overlaps(triangle, &cell)
+
+			};
+
+			if hit
+			{
+
+				covered(column, row);
+
+			}
+
+		}
+
+	}
+
+}
+
+//A fog of war grid tracking which cells are visible this frame and which have ever been seen
+pub struct FogGrid
+{
+
+	origin: Point,
+	cell_size: f32,
+	width: usize,
+	height: usize,
+	visible: Vec<bool>,
+	explored: Vec<bool>
+
+}
+
+impl FogGrid
+{
+
+	pub fn new(origin: Point, cell_size: f32, width: usize, height: usize) -> FogGrid
+	{
+
+		if cell_size <= 0.0
+		{
+
+			panic!("Cannot create fog grid with cells of non-positive size.");
+
+		}
+
+		return FogGrid { origin, cell_size, width, height, visible: vec![false; width * height], explored: vec![false; width * height] };
+
+	}
+
+	//Where the corner of cell (0, 0) lies
+	pub fn origin(&self) -> Point
+	{
+
+		return self.origin;
+
+	}
+
+	pub fn cell_size(&self) -> f32
+	{
+
+		return self.cell_size;
+
+	}
+
+	pub fn width(&self) -> usize
+	{
+
+		return self.width;
+
+	}
+
+	pub fn height(&self) -> usize
+	{
+
+		return self.height;
+
+	}
+
+	//Call at the start of each frame, before marking what every viewer can see
+	pub fn clear_visible(&mut self)
+	{
+
+		for cell in self.visible.iter_mut()
+		{
+
+			*cell = false;
+
+		}
+
+	}
+
+	//Marks the cells covered by a line of sight as visible and explored
+	pub fn mark(&mut self, triangles: &[Triangle], coverage: Coverage)
+	{
+
+		let width = self.width;
+
+		for triangle in triangles.iter()
+		{
+
+			let visible = &mut self.visible;
+			let explored = &mut self.explored;
+
+			for_each_covered_cell(triangle, self.origin, self.cell_size, self.width, self.height, coverage, |column, row|
+			{
+
+				visible[row * width + column] = true;
+				explored[row * width + column] = true;
+
+			});
+
+		}
+
+	}
+
+	pub fn is_visible(&self, column: usize, row: usize) -> bool
+	{
+
+		return self.visible[row * self.width + column];
+
+	}
+
+	pub fn is_explored(&self, column: usize, row: usize) -> bool
+	{
+
+		return self.explored[row * self.width + column];
+
+	}
+
+	//Row major, i.e. cell (column, row) is at index row * width + column
+	pub fn visible(&self) -> &[bool]
+	{
+
+		return &self.visible;
+
+	}
+
+	//Row major, i.e. cell (column, row) is at index row * width + column
+	pub fn explored(&self) -> &[bool]
+	{
+
+		return &self.explored;
+
+	}
+
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	#[test]
+	fn center_and_overlap()
+	{
+
+		//Covers the lower left half of a 4x4 grid of unit cells
+		let triangles = [Triangle::new(Point { x: 0.0, y: 0.0 }, Point { x: 4.0, y: 0.0 }, Point { x: 0.0, y: 4.0 })];
+
+		let mut center = FogGrid::new(Point { x: 0.0, y: 0.0 }, 1.0, 4, 4);
+		center.mark(&triangles, Coverage::Center);
+
+		let mut overlap = FogGrid::new(Point { x: 0.0, y: 0.0 }, 1.0, 4, 4);
+		overlap.mark(&triangles, Coverage::Overlap);
+
+		//Cells whose centers lie on the hypotenuse count as covered
+		assert_eq!(10, center.visible().iter().filter(|&&cell| cell).count());
+		assert!(center.is_visible(1, 2));
+		assert!(!center.is_visible(2, 2));
+
+		//Cells crossed by the hypotenuse overlap it, but cells it only touches at a corner do not
+		assert_eq!(10, overlap.visible().iter().filter(|&&cell| cell).count());
+		assert!(overlap.is_visible(3, 0));
+		assert!(!overlap.is_visible(3, 1));
+
+	}
+
+	#[test]
+	fn explored_accumulates()
+	{
+
+		let mut grid = FogGrid::new(Point { x: -2.0, y: -2.0 }, 2.0, 3, 3);
+		assert_eq!((3, 3, 2.0), (grid.width(), grid.height(), grid.cell_size()));
+
+		grid.mark(&[Triangle::new(Point { x: -2.0, y: -2.0 }, Point { x: 0.0, y: -2.0 }, Point { x: 0.0, y: 0.0 })], Coverage::Overlap);
+		assert!(grid.is_visible(0, 0));
+
+		grid.clear_visible();
+		grid.mark(&[Triangle::new(Point { x: 2.0, y: 2.0 }, Point { x: 4.0, y: 2.0 }, Point { x: 4.0, y: 4.0 })], Coverage::Overlap);
+
+		assert!(!grid.is_visible(0, 0));
+		assert!(grid.is_explored(0, 0));
+		assert!(grid.is_visible(2, 2));
+		assert!(grid.is_explored(2, 2));
+		assert_eq!(2, grid.explored().iter().filter(|&&cell| cell).count());
+
+	}
+
+	#[test]
+	fn outside_the_grid()
+	{
+
+		let mut grid = FogGrid::new(Point { x: 0.0, y: 0.0 }, 1.0, 2, 2);
+		grid.mark(&[Triangle::new(Point { x: -5.0, y: -5.0 }, Point { x: -1.0, y: -5.0 }, Point { x: -1.0, y: -1.0 })], Coverage::Overlap);
+		grid.mark(&[Triangle::new(Point { x: 5.0, y: 5.0 }, Point { x: 9.0, y: 5.0 }, Point { x: 9.0, y: 9.0 })], Coverage::Center);
+
+		assert!(grid.visible().iter().all(|&cell| !cell));
+
+	}
+
+}
@@ -0,0 +1,248 @@
+use super::fog::{for_each_covered_cell, Coverage};
+use super::sight::generate_full_line_of_sight;
+use super::space::{OccluderSource, Point, Polygon, Rectangle};
+
+use std::io::{self, Write};
+
+//Counts, for every cell of a grid, how many sampled viewer positions can see it
+//Cells seen from many places are exposed, while cells seen from few are good places to hide
+pub struct Heatmap
+{
+
+	origin: Point,
+	cell_size: f32,
+	width: usize,
+	height: usize,
+	samples: u32,
+	exposure: Vec<u32>,
+	last_seen: Vec<u32> //The last sample to see each cell, so that cells covered by several triangles are only counted once per sample
+
+}
+
+impl Heatmap
+{
+
+	pub fn new(origin: Point, cell_size: f32, width: usize, height: usize) -> Heatmap
+	{
+
+		if cell_size <= 0.0
+		{
+
+			panic!("Cannot create heatmap with cells of non-positive size.");
+
+		}
+
+		return Heatmap { origin, cell_size, width, height, samples: 0, exposure: vec![0; width * height], last_seen: vec![0; width * height] };
+
+	}
+
+	//Adds the cells whose centers can be seen in any direction from location
+	//The view is closed off just outside the heatmap, so cells need not be enclosed by occluders to be seen
+	pub fn add_viewer<'a, I>(&mut self, location: Point, occluders: I, mask: u32) where I: OccluderSource<'a>
+	{
+
+		let far = Point { x: self.origin.x + self.width as f32 * self.cell_size, y: self.origin.y + self.height as f32 * self.cell_size };
+		let bounds = Rectangle::new(
+			Point { x: self.origin.x.min(location.x) - self.cell_size, y: self.origin.y.min(location.y) - self.cell_size },
+			Point { x: far.x.max(location.x) + self.cell_size, y: far.y.max(location.y) + self.cell_size }
+		);
+
+		self.samples += 1;
+
+		let sample = self.samples;
+		let width = self.width;
+		let exposure = &mut self.exposure;
+		let last_seen = &mut self.last_seen;
+
+		for triangle in generate_full_line_of_sight(location, &bounds.close_off(occluders), mask).iter()
+		{
+
+			for_each_covered_cell(triangle, self.origin, self.cell_size, self.width, self.height, Coverage::Center, |column, row|
+			{
+
+				let index = row * width + column;
+
+				if last_seen[index] != sample
+				{
+
+					last_seen[index] = sample;
+					exposure[index] += 1;
+
+				}
+
+			});
+
+		}
+
+	}
+
+	//Adds a viewer at every point of a grid with the given spacing which lies inside walkable
+	pub fn sample<'a, I>(&mut self, walkable: &Polygon, spacing: f32, occluders: I, mask: u32) where I: OccluderSource<'a>
+	{
+
+		let occluders = occluders.into_occluders();
+
+		for location in walkable.grid(spacing).iter()
+		{
+
+			self.add_viewer(*location, occluders.clone(), mask);
+
+		}
+
+	}
+
+	//Where the corner of cell (0, 0) lies
+	pub fn origin(&self) -> Point
+	{
+
+		return self.origin;
+
+	}
+
+	pub fn cell_size(&self) -> f32
+	{
+
+		return self.cell_size;
+
+	}
+
+	pub fn width(&self) -> usize
+	{
+
+		return self.width;
+
+	}
+
+	pub fn height(&self) -> usize
+	{
+
+		return self.height;
+
+	}
+
+	//The number of viewers added so far
+	pub fn samples(&self) -> u32
+	{
+
+		return self.samples;
+
+	}
+
+	//The number of viewers which could see the cell
+	pub fn exposure(&self, column: usize, row: usize) -> u32
+	{
+
+		return self.exposure[row * self.width + column];
+
+	}
+
+	//Row major, i.e. cell (column, row) is at index row * width + column
+	pub fn counts(&self) -> &[u32]
+	{
+
+		return &self.exposure;
+
+	}
+
+	//Writes the heatmap as a plain text grayscale PGM image, where white cells were seen by every viewer and black cells by none
+	//Row 0 is written first, so the image is upside down if y points up in the world
+	pub fn write_pgm<W: Write>(&self, writer: &mut W) -> io::Result<()>
+	{
+
+		writeln!(writer, "P2")?;
+		writeln!(writer, "{} {}", self.width, self.height)?;
+		writeln!(writer, "255")?;
+
+		for row in 0..self.height
+		{
+
+			let shades: Vec<String> = (0..self.width).map(|column| (self.exposure(column, row) * 255 / self.samples.max(1)).to_string()).collect();
+			writeln!(writer, "{}", shades.join(" "))?;
+
+		}
+
+		return Ok(());
+
+	}
+
+	//Writes the raw counts as comma separated values, one line per row starting from row 0
+	pub fn write_csv<W: Write>(&self, writer: &mut W) -> io::Result<()>
+	{
+
+		for row in 0..self.height
+		{
+
+			let counts: Vec<String> = (0..self.width).map(|column| self.exposure(column, row).to_string()).collect();
+			writeln!(writer, "{}", counts.join(","))?;
+
+		}
+
+		return Ok(());
+
+	}
+
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::space::{Segment, ALL_LAYERS};
+
+	#[test]
+	fn exposure_behind_wall()
+	{
+
+		//A 4x2 room partly split by a wall rising from the middle of its floor
+		let mut walls = Vec::new();
+		walls.push(Segment::new(Point { x: 0.0, y: 0.0 }, Point { x: 4.0, y: 0.0 }));
+		walls.push(Segment::new(Point { x: 4.0, y: 0.0 }, Point { x: 4.0, y: 2.0 }));
+		walls.push(Segment::new(Point { x: 4.0, y: 2.0 }, Point { x: 0.0, y: 2.0 }));
+		walls.push(Segment::new(Point { x: 0.0, y: 2.0 }, Point { x: 0.0, y: 0.0 }));
+		walls.push(Segment::new(Point { x: 2.0, y: 0.0 }, Point { x: 2.0, y: 1.2 }));
+
+		let room = Polygon::new(vec![Point { x: 0.0, y: 0.0 }, Point { x: 4.0, y: 0.0 }, Point { x: 4.0, y: 2.0 }, Point { x: 0.0, y: 2.0 }]);
+
+		let mut heatmap = Heatmap::new(Point { x: 0.0, y: 0.0 }, 1.0, 4, 2);
+		heatmap.sample(&room, 1.0, &walls, ALL_LAYERS);
+
+		assert_eq!(8, heatmap.samples());
+
+		//The wall hides the cells at its foot from the far side of the room
+		assert_eq!(7, heatmap.exposure(1, 1));
+		assert_eq!(5, heatmap.exposure(0, 0));
+		assert_eq!(4, heatmap.exposure(1, 0));
+
+		let mut csv: Vec<u8> = Vec::new();
+		heatmap.write_csv(&mut csv).unwrap();
+		assert_eq!("5,4,4,5\n6,7,7,6\n", String::from_utf8(csv).unwrap());
+
+		let mut pgm: Vec<u8> = Vec::new();
+		heatmap.write_pgm(&mut pgm).unwrap();
+		assert_eq!("P2\n4 2\n255\n159 127 127 159\n191 223 223 191\n", String::from_utf8(pgm).unwrap());
+
+	}
+
+	#[test]
+	fn open_ground()
+	{
+
+		//The wall lies off the heatmap, so nothing stops the view over it
+		let walls = vec![Segment::new(Point { x: 5.0, y: -1.0 }, Point { x: 5.0, y: 5.0 })];
+
+		let mut heatmap = Heatmap::new(Point { x: 0.0, y: 0.0 }, 1.0, 4, 4);
+		assert_eq!((4, 4, 1.0), (heatmap.width(), heatmap.height(), heatmap.cell_size()));
+		heatmap.add_viewer(Point { x: 0.5, y: 0.5 }, &walls, ALL_LAYERS);
+
+		assert!(heatmap.counts().iter().all(|&count| count == 1));
+
+		//Viewers off the heatmap still see onto it
+		heatmap.add_viewer(Point { x: -3.0, y: 2.0 }, &walls, ALL_LAYERS);
+		heatmap.add_viewer(Point { x: 8.0, y: 2.0 }, &walls, ALL_LAYERS);
+
+		assert!(heatmap.counts().iter().all(|&count| count == 2));
+
+	}
+
+}
@@ -0,0 +1,48 @@
+//The line of sight algorithm along with everything built on top of it, shared by the example application and the benchmarks
+
+pub mod space;
+pub mod sight;
+pub mod shadow;
+pub mod region;
+pub mod fog;
+pub mod tiles;
+pub mod cleanup;
+pub mod scene;
+pub mod atan;
+pub mod batch;
+pub mod agents;
+pub mod exposure;
+pub mod heatmap;
+pub mod placement;
+pub mod cover;
+pub mod light;
+
+use space::{Point, Segment};
+
+pub const WORLD_WIDTH: f32 = 700.0;
+pub const WORLD_HEIGHT: f32 = 500.0;
+
+//Some example walls to obstruct our view
+pub fn demo_walls() -> Vec<Segment>
+{
+
+    let mut walls: Vec<Segment> = Vec::new();
+    walls.push(Segment::new(Point { x: 5.0, y: 5.0 }, Point { x: 5.0 + WORLD_WIDTH, y: 5.0 }));
+    walls.push(Segment::new(Point { x: 5.0 + WORLD_WIDTH, y: 5.0 }, Point { x: 5.0 + WORLD_WIDTH, y: 5.0 + WORLD_HEIGHT }));
+    walls.push(Segment::new(Point { x: 5.0 + WORLD_WIDTH, y: 5.0 + WORLD_HEIGHT }, Point { x: 5.0, y: 5.0 + WORLD_HEIGHT }));
+    walls.push(Segment::new(Point { x: 5.0, y: 5.0 + WORLD_HEIGHT }, Point { x: 5.0, y: 5.0 }));
+    walls.push(Segment::new(Point { x: 100.0, y: 50.0 }, Point { x: 50.0, y: 100.0 }));
+    walls.push(Segment::new(Point { x: 200.0, y: 450.0 }, Point { x: 400.0, y: 450.0 }));
+    walls.push(Segment::new(Point { x: 300.0, y: 350.0 }, Point { x: 350.0, y: 425.0 }));
+    walls.push(Segment::new(Point { x: 400.0, y: 10.0 }, Point { x: 400.0, y: 50.0 }));
+    walls.push(Segment::new(Point { x: 400.0, y: 50.0 }, Point { x: 600.0, y: 250.0 }));
+    walls.push(Segment::new(Point { x: 50.0, y: 300.0 }, Point { x: 75.0, y: 300.0 }));
+    walls.push(Segment::new(Point { x: 75.0, y: 300.0 }, Point { x: 75.0, y: 325.0 }));
+    walls.push(Segment::new(Point { x: 75.0, y: 325.0 }, Point { x: 50.0, y: 325.0 }));
+    walls.push(Segment::new(Point { x: 50.0, y: 325.0 }, Point { x: 50.0, y: 300.0 }));
+    walls.push(Segment::new(Point { x: 280.0, y: 240.0}, Point { x: 280.0, y: 260.0 }));
+    walls.push(Segment::new(Point { x: 450.0, y: 5.0 + WORLD_HEIGHT * 0.5 }, Point {x : 700.0, y: 5.0 + WORLD_HEIGHT * 0.5 }));
+
+    return walls;
+
+}
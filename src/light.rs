@@ -0,0 +1,92 @@
+use super::region;
+use super::sight::{generate_full_line_of_sight, is_visible};
+use super::space::{OccluderSource, Point, Polygon, Triangle};
+
+//The points an area light is sampled at, spread evenly around its rim
+fn light_samples(center: Point, radius: f32, samples: usize) -> Vec<Point>
+{
+
+	if samples < 3
+	{
+
+		panic!("Cannot sample area light at fewer than three points.");
+
+	}
+
+	return Polygon::circle(center, radius, samples).vertices;
+
+}
+
+//Splits what a round light of the given radius illuminates into the fully lit area, seen from every part of the light,
+//and the penumbra, seen from some of it but not all. Anything in neither is in the umbra and gets no light at all
+//The light is sampled at samples points around its rim, which must not lie on any occluder's endpoints
+//Returns (lit, penumbra), each as non-overlapping counterclockwise polygons
+pub fn generate_soft_shadows<'a, I>(center: Point, radius: f32, samples: usize, occluders: I, mask: u32) -> (Vec<Polygon>, Vec<Polygon>) where I: OccluderSource<'a>
+{
+
+	let occluders = occluders.into_occluders();
+	let regions: Vec<Vec<Triangle>> = light_samples(center, radius, samples).iter().map(|&location| generate_full_line_of_sight(location, occluders.clone(), mask)).collect();
+
+	return (region::intersection(&regions), region::coverage_between(&regions, 1, samples - 1));
+
+}
+
+//Returns the fraction of the light reaching point, from 0 in the umbra to 1 where it is fully lit
+//Useful for shading the vertices of a mesh rather than building the penumbra itself
+pub fn light_intensity<'a, I>(center: Point, radius: f32, samples: usize, point: Point, occluders: I, mask: u32) -> f32 where I: OccluderSource<'a>
+{
+
+	let occluders = occluders.into_occluders();
+	let lights = light_samples(center, radius, samples);
+
+	let lit = lights.iter().filter(|&&location| is_visible(location, point, occluders.clone(), mask)).count();
+
+	return lit as f32 / samples as f32;
+
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::space::{Segment, ALL_LAYERS};
+
+	#[test]
+	fn umbra_and_penumbra()
+	{
+
+		let mut walls = Vec::new();
+		walls.push(Segment::new(Point { x: 0.0, y: 0.0 }, Point { x: 10.0, y: 0.0 }));
+		walls.push(Segment::new(Point { x: 10.0, y: 0.0 }, Point { x: 10.0, y: 10.0 }));
+		walls.push(Segment::new(Point { x: 10.0, y: 10.0 }, Point { x: 0.0, y: 10.0 }));
+		walls.push(Segment::new(Point { x: 0.0, y: 10.0 }, Point { x: 0.0, y: 0.0 }));
+		walls.push(Segment::new(Point { x: 5.0, y: 4.0 }, Point { x: 5.0, y: 6.0 }));
+
+		let center = Point { x: 2.0, y: 5.0 };
+		let (lit, penumbra) = generate_soft_shadows(center, 0.5, 8, &walls, ALL_LAYERS);
+
+		let inside = |polygons: &Vec<Polygon>, point: Point| polygons.iter().any(|polygon| polygon.contains(point));
+
+		//Straight behind the pillar is the umbra
+		let umbra = Point { x: 8.0, y: 5.0 };
+		assert!(!inside(&lit, umbra) && !inside(&penumbra, umbra));
+		assert_eq!(0.0, light_intensity(center, 0.5, 8, umbra, &walls, ALL_LAYERS));
+
+		//Along the edge of the pillar's hard shadow, only part of the light gets through
+		let edge = Point { x: 8.0, y: 7.0 };
+		assert!(inside(&penumbra, edge) && !inside(&lit, edge));
+		let intensity = light_intensity(center, 0.5, 8, edge, &walls, ALL_LAYERS);
+		assert!(intensity > 0.0 && intensity < 1.0);
+
+		let open = Point { x: 3.0, y: 8.0 };
+		assert!(inside(&lit, open) && !inside(&penumbra, open));
+		assert_eq!(1.0, light_intensity(center, 0.5, 8, open, &walls, ALL_LAYERS));
+
+		//The umbra is narrower than the hard shadow a point light at the center would cast, a trapezoid of area 55/3
+		let area: f32 = lit.iter().chain(penumbra.iter()).map(|polygon| polygon.area()).sum();
+		assert!(area < 100.0 && area > 100.0 - 55.0 / 3.0);
+
+	}
+
+}
@@ -6,13 +6,9 @@ use ggez::graphics::{DrawMode, DrawParam, Mesh};
 use std::path;
 use std::env;
 
-mod space;
-mod sight;
-
-use space::{Point, Segment, Triangle};
-
-const WORLD_WIDTH: f32 = 700.0;
-const WORLD_HEIGHT: f32 = 500.0;
+use line_of_sight::sight::VisibilityContext;
+use line_of_sight::space::{Point, Segment, Triangle, ALL_LAYERS};
+use line_of_sight::{demo_walls, WORLD_HEIGHT, WORLD_WIDTH};
 
 //Used for rotating the line of sight each frame
 const SIN_THETA: f32 = 0.01308959557;
@@ -22,6 +18,7 @@ struct State
 {
 
     walls: Vec<Segment>,
+    context: VisibilityContext,
     sight: Vec<Triangle>,
     location: Point,
     upper: Point,
@@ -37,23 +34,7 @@ impl State
     pub fn new(ctx: &mut Context) -> GameResult<State>
     {
 
-        //Some example walls to obstruct our view
-        let mut walls: Vec<Segment> = Vec::new();
-        walls.push(Segment::new(Point { x: 5.0, y: 5.0 }, Point { x: 5.0 + WORLD_WIDTH, y: 5.0 }));
-        walls.push(Segment::new(Point { x: 5.0 + WORLD_WIDTH, y: 5.0 }, Point { x: 5.0 + WORLD_WIDTH, y: 5.0 + WORLD_HEIGHT }));
-        walls.push(Segment::new(Point { x: 5.0 + WORLD_WIDTH, y: 5.0 + WORLD_HEIGHT }, Point { x: 5.0, y: 5.0 + WORLD_HEIGHT }));
-        walls.push(Segment::new(Point { x: 5.0, y: 5.0 + WORLD_HEIGHT }, Point { x: 5.0, y: 5.0 }));
-        walls.push(Segment::new(Point { x: 100.0, y: 50.0 }, Point { x: 50.0, y: 100.0 }));
-        walls.push(Segment::new(Point { x: 200.0, y: 450.0 }, Point { x: 400.0, y: 450.0 }));
-        walls.push(Segment::new(Point { x: 300.0, y: 350.0 }, Point { x: 350.0, y: 425.0 }));
-        walls.push(Segment::new(Point { x: 400.0, y: 10.0 }, Point { x: 400.0, y: 50.0 }));
-        walls.push(Segment::new(Point { x: 400.0, y: 50.0 }, Point { x: 600.0, y: 250.0 }));
-        walls.push(Segment::new(Point { x: 50.0, y: 300.0 }, Point { x: 75.0, y: 300.0 }));
-        walls.push(Segment::new(Point { x: 75.0, y: 300.0 }, Point { x: 75.0, y: 325.0 }));
-        walls.push(Segment::new(Point { x: 75.0, y: 325.0 }, Point { x: 50.0, y: 325.0 }));
-        walls.push(Segment::new(Point { x: 50.0, y: 325.0 }, Point { x: 50.0, y: 300.0 }));
-        walls.push(Segment::new(Point { x: 280.0, y: 240.0}, Point { x: 280.0, y: 260.0 }));
-        walls.push(Segment::new(Point { x: 450.0, y: 5.0 + WORLD_HEIGHT * 0.5 }, Point {x : 700.0, y: 5.0 + WORLD_HEIGHT * 0.5 }));
+        let walls = demo_walls();
 
         let mb = &mut graphics::MeshBuilder::new();
         for segment in walls.iter()
@@ -94,6 +75,7 @@ impl State
         {
 
             walls: walls,
+            context: VisibilityContext::new(),
             sight: Vec::new(),
             location: Point { x: 5.0 + WORLD_WIDTH * 0.5, y: 5.0 + WORLD_HEIGHT * 0.5 },
             upper: Point { x: (3f32).sqrt(), y: 1.0 },
@@ -121,7 +103,7 @@ impl event::EventHandler for State
         self.lower.y = self.lower.x * SIN_THETA + self.lower.y * COS_THETA;
 
         //Generate our new line of sight
-        self.sight = sight::generate_line_of_sight(self.location, self.upper, self.lower, &self.walls); 
+        self.context.compute_into(self.location, self.upper, self.lower, &self.walls, ALL_LAYERS, &mut self.sight);
         
         let mb = &mut graphics::MeshBuilder::new();
         for triangle in self.sight.iter()
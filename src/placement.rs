@@ -0,0 +1,223 @@
+use super::agents::Agent;
+use super::sight::is_visible;
+use super::space::{OccluderSource, Point, Polygon};
+
+//Chooses where to put guards or cameras so that together they see as much of walkable as possible, using as few as it can
+//Candidate positions and the points which need watching both come from a grid with the given spacing inside walkable
+//Each candidate tries every (upper, lower) cone in cones, each narrower than pi, or sees all around if cones is empty
+//Candidates are picked greedily, each time taking the one which sees the most points no earlier pick sees, until every point is seen,
+//no candidate would help, or max_guards have been placed. Returns the guards along with the fraction of points they see
+pub fn place_guards<'a, I>(walkable: &Polygon, spacing: f32, cones: &[(Point, Point)], occluders: I, mask: u32, max_guards: usize) -> (Vec<Agent>, f32) where I: OccluderSource<'a>
+{
+
+	let occluders = occluders.into_occluders();
+	let points = walkable.grid(spacing);
+
+	if points.is_empty()
+	{
+
+		return (Vec::new(), 0.0);
+
+	}
+
+	let mut candidates: Vec<Agent> = Vec::new();
+	for location in points.iter()
+	{
+
+		if cones.is_empty()
+		{
+
+			candidates.push(Agent { location: *location, facing: None });
+
+		}
+
+		for cone in cones.iter()
+		{
+
+			candidates.push(Agent { location: *location, facing: Some(*cone) });
+
+		}
+
+	}
+
+	//Every location is raycast against every point once, no matter how many cones it tries
+	let mut visible = vec![false; points.len() * points.len()];
+	for i in 0..points.len()
+	{
+
+		visible[i * points.len() + i] = true;
+
+		for j in i + 1..points.len()
+		{
+
+			if is_visible(points[i], points[j], occluders.clone(), mask)
+			{
+
+				visible[i * points.len() + j] = true;
+				visible[j * points.len() + i] = true;
+
+			}
+
+		}
+
+	}
+
+	let per_location = cones.len().max(1);
+	let watched: Vec<Vec<usize>> = candidates.iter().enumerate().map(|(index, candidate)|
+	{
+
+		let from = index / per_location;
+
+		return (0..points.len()).filter(|&to| visible[from * points.len() + to] && (from == to || candidate.faces(points[to]))).collect();
+
+	}).collect();
+
+	let mut seen = vec![false; points.len()];
+	let mut seen_count = 0;
+	let mut guards: Vec<Agent> = Vec::new();
+
+	while guards.len() < max_guards && seen_count < points.len()
+	{
+
+		let mut best: Option<(usize, usize)> = None;
+
+		for (index, points_watched) in watched.iter().enumerate()
+		{
+
+			let gain = points_watched.iter().filter(|&&point| !seen[point]).count();
+
+			if gain > 0 && best.is_none_or(|(_, best_gain)| gain > best_gain)
+			{
+
+				best = Some((index, gain));
+
+			}
+
+		}
+
+		let index = match best
+		{
+
+			Some((index, _)) => index,
+			None => break
+
+		};
+
+		for &point in watched[index].iter()
+		{
+
+			if !seen[point]
+			{
+
+				seen[point] = true;
+				seen_count += 1;
+
+			}
+
+		}
+
+		guards.push(candidates[index]);
+
+	}
+
+	return (guards, seen_count as f32 / points.len() as f32);
+
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::space::{Segment, ALL_LAYERS};
+
+	fn two_rooms() -> (Vec<Segment>, Polygon)
+	{
+
+		//A 10x4 room split by a wall with a gap at the top
+		let mut walls = Vec::new();
+		walls.push(Segment::new(Point { x: 0.0, y: 0.0 }, Point { x: 10.0, y: 0.0 }));
+		walls.push(Segment::new(Point { x: 10.0, y: 0.0 }, Point { x: 10.0, y: 4.0 }));
+		walls.push(Segment::new(Point { x: 10.0, y: 4.0 }, Point { x: 0.0, y: 4.0 }));
+		walls.push(Segment::new(Point { x: 0.0, y: 4.0 }, Point { x: 0.0, y: 0.0 }));
+		walls.push(Segment::new(Point { x: 5.0, y: 0.0 }, Point { x: 5.0, y: 3.0 }));
+
+		let walkable = Polygon::new(vec![Point { x: 0.0, y: 0.0 }, Point { x: 10.0, y: 0.0 }, Point { x: 10.0, y: 4.0 }, Point { x: 0.0, y: 4.0 }]);
+
+		return (walls, walkable);
+
+	}
+
+	#[test]
+	fn cover_two_rooms()
+	{
+
+		let (walls, walkable) = two_rooms();
+
+		let (guards, fraction) = place_guards(&walkable, 1.0, &[], &walls, ALL_LAYERS, 10);
+		assert_eq!(1.0, fraction);
+		assert_eq!(2, guards.len());
+
+		//Every point is watched by one of the guards
+		for point in walkable.grid(1.0).iter()
+		{
+
+			assert!(guards.iter().any(|guard| is_visible(guard.location, *point, &walls, ALL_LAYERS)));
+
+		}
+
+		//A single guard cannot see round the wall
+		let (guards, fraction) = place_guards(&walkable, 1.0, &[], &walls, ALL_LAYERS, 1);
+		assert_eq!(1, guards.len());
+		assert!(fraction < 1.0 && fraction > 0.5);
+
+	}
+
+	#[test]
+	fn cameras_with_cones()
+	{
+
+		let (walls, walkable) = two_rooms();
+
+		//Cameras can face up and to the right, up and to the left, and so on
+		let cones = [
+			(Point { x: 0.0, y: 1.0 }, Point { x: 1.0, y: 0.0 }),
+			(Point { x: -1.0, y: 0.0 }, Point { x: 0.0, y: 1.0 }),
+			(Point { x: 0.0, y: -1.0 }, Point { x: -1.0, y: 0.0 }),
+			(Point { x: 1.0, y: 0.0 }, Point { x: 0.0, y: -1.0 })
+		];
+
+		let (cameras, fraction) = place_guards(&walkable, 1.0, &cones, &walls, ALL_LAYERS, 10);
+		assert_eq!(1.0, fraction);
+		assert!(cameras.iter().all(|camera| camera.facing.is_some()));
+
+		for point in walkable.grid(1.0).iter()
+		{
+
+			assert!(cameras.iter().any(|camera| (camera.location == *point || camera.faces(*point)) && is_visible(camera.location, *point, &walls, ALL_LAYERS)));
+
+		}
+
+		//Facing up and to the right, one camera in the bottom left corner of each room is enough
+		let (cameras, fraction) = place_guards(&walkable, 1.0, &cones[..1], &walls, ALL_LAYERS, 10);
+		assert_eq!(1.0, fraction);
+		assert_eq!(2, cameras.len());
+		assert!(cameras.contains(&Agent { location: Point { x: 0.5, y: 0.5 }, facing: Some(cones[0]) }));
+		assert!(cameras.contains(&Agent { location: Point { x: 5.5, y: 0.5 }, facing: Some(cones[0]) }));
+
+	}
+
+	#[test]
+	fn unenclosed()
+	{
+
+		//Only the dividing wall, with nothing round the outside for rays to stop on
+		let (walls, walkable) = two_rooms();
+
+		let (guards, fraction) = place_guards(&walkable, 1.0, &[], &walls[4..], ALL_LAYERS, 10);
+		assert_eq!(1.0, fraction);
+		assert_eq!(2, guards.len());
+
+	}
+
+}
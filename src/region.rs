@@ -0,0 +1,614 @@
+use super::space::{Point, Polygon, Triangle, FLOATING_POINT_ERROR};
+
+//A triangle edge which is not vertical, stored from left to right
+struct Edge
+{
+
+	left: Point,
+	right: Point
+
+}
+
+impl Edge
+{
+
+	fn y_at(&self, x: f32) -> f32
+	{
+
+		//Endpoints are merged with any within FLOATING_POINT_ERROR of them, so x can lie just beyond the edge
+		let s = ((x - self.left.x) / (self.right.x - self.left.x)).clamp(0.0, 1.0);
+
+		return self.left.y + (self.right.y - self.left.y) * s;
+
+	}
+
+	//Returns the x coordinate at which the edges cross, if they cross away from their endpoints
+	fn crossing(&self, other: &Edge) -> Option<f32>
+	{
+
+		let direction = self.right - self.left;
+		let other_direction = other.right - other.left;
+
+		let denominator = direction.x * other_direction.y - direction.y * other_direction.x;
+		if denominator.abs() < FLOATING_POINT_ERROR
+		{
+
+			return None;
+
+		}
+
+		let offset = other.left - self.left;
+		let s = (offset.x * other_direction.y - offset.y * other_direction.x) / denominator;
+		let t = (offset.x * direction.y - offset.y * direction.x) / denominator;
+
+		if s <= 0.0 || s >= 1.0 || t <= 0.0 || t >= 1.0
+		{
+
+			return None;
+
+		}
+
+		return Some(self.left.x + direction.x * s);
+
+	}
+
+}
+
+//Where a region starts or stops covering a slab, as the y values of one of its edges at the slab's left, middle and right
+struct Boundary
+{
+
+	left: f32,
+	middle: f32,
+	right: f32,
+	region: usize,
+	delta: i32
+
+}
+
+//A polygon under construction, growing to the right one slab at a time
+struct Piece
+{
+
+	lower: Vec<Point>,
+	upper: Vec<Point>
+
+}
+
+impl Piece
+{
+
+	fn extend(chain: &mut Vec<Point>, point: Point)
+	{
+
+		//Drop the previous point when it is collinear with its neighbours
+		if chain.len() >= 2
+		{
+
+			let a = chain[chain.len() - 2];
+			let b = chain[chain.len() - 1];
+
+			let cross = (b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x);
+			if cross.abs() < FLOATING_POINT_ERROR
+			{
+
+				chain.pop();
+
+			}
+
+		}
+
+		chain.push(point);
+
+	}
+
+	fn into_polygon(self) -> Polygon
+	{
+
+		let mut vertices = self.lower;
+		vertices.extend(self.upper.into_iter().rev());
+
+		return Polygon::new(vertices);
+
+	}
+
+}
+
+//Adds the edges of a counterclockwise polygon, along with the change in region's winding count when crossing each one upwards
+//Its bottom edges run to the right and its top edges to the left
+fn push_edges(vertices: &[Point], region: usize, edges: &mut Vec<(Edge, usize, i32)>)
+{
+
+	for i in 0..vertices.len()
+	{
+
+		let start = vertices[i];
+		let end = vertices[(i + 1) % vertices.len()];
+
+		if start.x < end.x
+		{
+
+			edges.push((Edge { left: start, right: end }, region, 1));
+
+		}
+		else
+		{
+
+			edges.push((Edge { left: end, right: start }, region, -1));
+
+		}
+
+	}
+
+}
+
+//Returns the parts of the plane where the number of distinct regions covering it satisfies inside
+fn decompose<F>(regions: &[Vec<Triangle>], inside: F) -> Vec<Polygon> where F: Fn(usize) -> bool
+{
+
+	let mut edges: Vec<(Edge, usize, i32)> = Vec::new();
+
+	for (region, triangles) in regions.iter().enumerate()
+	{
+
+		for triangle in triangles.iter()
+		{
+
+			if triangle.area() < FLOATING_POINT_ERROR
+			{
+
+				continue;
+
+			}
+
+			let [a, b, c] = triangle.vertices;
+			let vertices = if (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x) > 0.0 { [a, b, c] } else { [a, c, b] };
+
+			push_edges(&vertices, region, &mut edges);
+
+		}
+
+	}
+
+	return sweep(edges, regions.len(), inside);
+
+}
+
+//Splits the plane into vertical slabs in which no edges cross, then keeps the parts of each slab where the number of distinct
+//regions covering it satisfies inside. Trapezoids which line up across slabs are joined into a single polygon
+fn sweep<F>(mut edges: Vec<(Edge, usize, i32)>, region_count: usize, inside: F) -> Vec<Polygon> where F: Fn(usize) -> bool
+{
+
+	//Neighbouring triangles of a region share edges which cancel out, so only the outline of each region is swept
+	let key = |(edge, region, _): &(Edge, usize, i32)| (*region, edge.left.x.to_bits(), edge.left.y.to_bits(), edge.right.x.to_bits(), edge.right.y.to_bits());
+	edges.sort_unstable_by_key(key);
+
+	let mut outline: Vec<(Edge, usize, i32)> = Vec::new();
+	for edge in edges.into_iter()
+	{
+
+		match outline.last_mut()
+		{
+
+			Some(last) if key(last) == key(&edge) => last.2 += edge.2,
+			_ => outline.push(edge)
+
+		}
+
+	}
+
+	outline.retain(|(_, _, delta)| *delta != 0);
+	let edges = outline;
+
+	let mut xs: Vec<f32> = Vec::new();
+	for (edge, _, _) in edges.iter()
+	{
+
+		xs.push(edge.left.x);
+		xs.push(edge.right.x);
+
+	}
+
+	xs.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+	xs.dedup_by(|a, b| (*a - *b).abs() < FLOATING_POINT_ERROR);
+
+	//Endpoints merged into the same x are treated as lying on it, so that edges meeting at a vertex agree on which slabs they span
+	//Each entry holds the indices in xs of the edge's ends, and edges which start and end at the same x, which span no slab, are dropped
+	let column = |x: f32| xs.partition_point(|&boundary| boundary <= x) - 1;
+	let mut sweep: Vec<(usize, usize, usize)> = edges.iter().enumerate().map(|(index, (edge, _, _))| (column(edge.left.x), column(edge.right.x), index)).filter(|(first, last, _)| first < last).collect();
+	sweep.sort_unstable();
+
+	let mut polygons: Vec<Polygon> = Vec::new();
+	let mut open: Vec<Piece> = Vec::new();
+
+	let mut counts: Vec<i32> = vec![0; region_count];
+	let mut boundaries: Vec<Boundary> = Vec::new();
+
+	let mut active: Vec<(usize, usize)> = Vec::new(); //The last column and index of each edge spanning the current slab
+	let mut next_edge = 0;
+
+	let mut x0 = match xs.first()
+	{
+
+		Some(x) => *x,
+		None => return polygons
+
+	};
+
+	//Slabs run from x0 to the next column, unless two edges cross in between
+	let mut k = 1;
+	while k < xs.len()
+	{
+
+		let mut x1 = xs[k];
+
+		while next_edge < sweep.len() && sweep[next_edge].0 < k
+		{
+
+			let (_, last, index) = sweep[next_edge];
+			active.push((last, index));
+			next_edge += 1;
+
+		}
+
+		active.retain(|&(last, _)| last >= k);
+
+		//Edges swap places at a crossing, so the first crossing is between edges which are neighbours just before it
+		//Crossings within FLOATING_POINT_ERROR of x0 are treated as being at x0, so the order is taken from just after them
+		let after = x0 + FLOATING_POINT_ERROR;
+		active.sort_by(|&(_, a), &(_, b)|
+		{
+
+			let (a, b) = (&edges[a].0, &edges[b].0);
+
+			return a.y_at(after).partial_cmp(&b.y_at(after)).unwrap().then(a.y_at(x1).partial_cmp(&b.y_at(x1)).unwrap());
+
+		});
+
+		for pair in active.windows(2)
+		{
+
+			if let Some(x) = edges[pair[0].1].0.crossing(&edges[pair[1].1].0)
+			{
+
+				if x > after && x < x1 - FLOATING_POINT_ERROR
+				{
+
+					x1 = x;
+
+				}
+
+			}
+
+		}
+
+		//End the slab at the crossing and carry on from there, or move on to the next endpoint
+		if x1 == xs[k]
+		{
+
+			k += 1;
+
+		}
+
+		let middle = (x0 + x1) * 0.5;
+
+		boundaries.clear();
+		for &(_, index) in active.iter()
+		{
+
+			let (edge, region, delta) = &edges[index];
+			boundaries.push(Boundary { left: edge.y_at(x0), middle: edge.y_at(middle), right: edge.y_at(x1), region: *region, delta: *delta });
+
+		}
+
+		//Sort upwards, entering triangles before leaving others so that touching triangles leave no seam
+		boundaries.sort_unstable_by(|a, b| a.middle.partial_cmp(&b.middle).unwrap().then(b.delta.cmp(&a.delta)));
+
+		let mut next_open: Vec<Piece> = Vec::new();
+		let mut distinct = 0;
+		let mut start: Option<(f32, f32)> = None;
+
+		for boundary in boundaries.iter()
+		{
+
+			let count = &mut counts[boundary.region];
+			let before = *count;
+			*count += boundary.delta;
+
+			if before == 0 && *count != 0
+			{
+
+				distinct += 1;
+
+			}
+			else if before != 0 && *count == 0
+			{
+
+				distinct -= 1;
+
+			}
+
+			match start
+			{
+
+				None if inside(distinct) => start = Some((boundary.left, boundary.right)),
+				Some((bottom_left, bottom_right)) if !inside(distinct) =>
+				{
+
+					start = None;
+
+					if boundary.left - bottom_left < FLOATING_POINT_ERROR && boundary.right - bottom_right < FLOATING_POINT_ERROR
+					{
+
+						continue;
+
+					}
+
+					//Continue a polygon from the previous slab if this trapezoid lines up with it
+					let matching = open.iter().position(|piece|
+					{
+
+						let lower = piece.lower.last().unwrap();
+						let upper = piece.upper.last().unwrap();
+
+						return (lower.x - x0).abs() < FLOATING_POINT_ERROR && (lower.y - bottom_left).abs() < FLOATING_POINT_ERROR && (upper.y - boundary.left).abs() < FLOATING_POINT_ERROR;
+
+					});
+
+					let mut piece = match matching
+					{
+
+						Some(index) => open.swap_remove(index),
+						None => Piece { lower: vec![Point { x: x0, y: bottom_left }], upper: vec![Point { x: x0, y: boundary.left }] }
+
+					};
+
+					Piece::extend(&mut piece.lower, Point { x: x1, y: bottom_right });
+					Piece::extend(&mut piece.upper, Point { x: x1, y: boundary.right });
+
+					next_open.push(piece);
+
+				},
+				_ => ()
+
+			}
+
+		}
+
+		//Anything not continued into this slab is finished
+		polygons.extend(open.drain(..).map(|piece| piece.into_polygon()));
+		open = next_open;
+
+		x0 = x1;
+
+	}
+
+	polygons.extend(open.into_iter().map(|piece| piece.into_polygon()));
+
+	return polygons;
+
+}
+
+//Returns the area covered by at least one of the regions as non-overlapping counterclockwise polygons
+//Each half of the regions is merged on its own first, so that edges hidden inside one half are never swept against the other
+pub fn union(regions: &[Vec<Triangle>]) -> Vec<Polygon>
+{
+
+	if regions.len() <= 2
+	{
+
+		return decompose(regions, |distinct| distinct > 0);
+
+	}
+
+	let (first, second) = regions.split_at(regions.len() / 2);
+
+	let mut edges: Vec<(Edge, usize, i32)> = Vec::new();
+	for (region, half) in [union(first), union(second)].iter().enumerate()
+	{
+
+		for polygon in half.iter()
+		{
+
+			push_edges(&polygon.vertices, region, &mut edges);
+
+		}
+
+	}
+
+	return sweep(edges, 2, |distinct| distinct > 0);
+
+}
+
+//Returns the area covered by every one of the regions as non-overlapping counterclockwise polygons
+pub fn intersection(regions: &[Vec<Triangle>]) -> Vec<Polygon>
+{
+
+	return coverage(regions, regions.len());
+
+}
+
+//Returns the area covered by at least k of the regions as non-overlapping counterclockwise polygons
+//Asking for zero regions is treated the same as asking for one, as the plane itself is unbounded
+pub fn coverage(regions: &[Vec<Triangle>], k: usize) -> Vec<Polygon>
+{
+
+	let k = k.max(1);
+
+	return decompose(regions, |distinct| distinct >= k);
+
+}
+
+//Returns the area covered by at least min and at most max of the regions as non-overlapping counterclockwise polygons
+//As with coverage, a min of zero is treated as one
+pub fn coverage_between(regions: &[Vec<Triangle>], min: usize, max: usize) -> Vec<Polygon>
+{
+
+	let min = min.max(1);
+
+	return decompose(regions, |distinct| distinct >= min && distinct <= max);
+
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::demo_walls;
+	use crate::sight::generate_line_of_sight;
+	use crate::space::ALL_LAYERS;
+
+	use rand::prelude::*;
+
+	fn square(min: Point, size: f32) -> Vec<Triangle>
+	{
+
+		let a = min;
+		let b = Point { x: min.x + size, y: min.y };
+		let c = Point { x: min.x + size, y: min.y + size };
+		let d = Point { x: min.x, y: min.y + size };
+
+		return vec![Triangle::new(a, b, c), Triangle::new(a, c, d)];
+
+	}
+
+	fn total_area(polygons: &[Polygon]) -> f32
+	{
+
+		return polygons.iter().map(|polygon| polygon.signed_area()).sum();
+
+	}
+
+	#[test]
+	fn union_of_overlapping_squares()
+	{
+
+		let regions = vec![square(Point { x: 0.0, y: 0.0 }, 2.0), square(Point { x: 1.0, y: 1.0 }, 2.0)];
+		let polygons = union(&regions);
+
+		assert!((total_area(&polygons) - 7.0).abs() < FLOATING_POINT_ERROR);
+		assert!(polygons.iter().all(|polygon| polygon.signed_area() > 0.0));
+
+	}
+
+	#[test]
+	fn union_joins_slabs()
+	{
+
+		//A single square split into triangles comes back as one polygon
+		let regions = vec![square(Point { x: 0.0, y: 0.0 }, 2.0)];
+		let polygons = union(&regions);
+
+		assert_eq!(1, polygons.len());
+		assert_eq!(4, polygons[0].vertices.len());
+		assert!((polygons[0].signed_area() - 4.0).abs() < FLOATING_POINT_ERROR);
+
+	}
+
+	#[test]
+	fn union_of_disjoint_squares()
+	{
+
+		let regions = vec![square(Point { x: 0.0, y: 0.0 }, 1.0), square(Point { x: 3.0, y: 3.0 }, 1.0), Vec::new()];
+		let polygons = union(&regions);
+
+		assert_eq!(2, polygons.len());
+		assert!((total_area(&polygons) - 2.0).abs() < FLOATING_POINT_ERROR);
+
+	}
+
+	//Cameras watching the demo world from main.rs
+	fn cameras() -> Vec<Vec<Triangle>>
+	{
+
+		let walls = demo_walls();
+
+		let mut regions = Vec::new();
+		regions.push(generate_line_of_sight(Point { x: 150.0, y: 250.0 }, Point { x: 1.0, y: 1.0 }, Point { x: 1.0, y: -1.0 }, &walls, ALL_LAYERS));
+		regions.push(generate_line_of_sight(Point { x: 650.0, y: 400.0 }, Point { x: -1.0, y: -1.0 }, Point { x: -1.0, y: 0.2 }, &walls, ALL_LAYERS));
+		regions.push(generate_line_of_sight(Point { x: 355.0, y: 480.0 }, Point { x: -1.0, y: -1.0 }, Point { x: 1.0, y: -1.0 }, &walls, ALL_LAYERS));
+
+		return regions;
+
+	}
+
+	fn fan_area(triangles: &[Triangle]) -> f32
+	{
+
+		return triangles.iter().map(|triangle| triangle.area()).sum();
+
+	}
+
+	#[test]
+	fn demo_intersection()
+	{
+
+		let regions = cameras();
+		let pair = &regions[0..2];
+
+		let both = intersection(pair);
+		let either = union(pair);
+
+		//Inclusion-exclusion holds for the areas of two cameras
+		let expected = fan_area(&pair[0]) + fan_area(&pair[1]);
+		assert!((total_area(&both) + total_area(&either) - expected).abs() / expected < 0.001);
+
+		//Both cameras see the middle of the room, but the wall at x = 280 hides the point behind it from the first
+		assert!(both.iter().any(|polygon| polygon.contains(Point { x: 400.0, y: 300.0 })));
+		assert!(!both.iter().any(|polygon| polygon.contains(Point { x: 300.0, y: 250.0 })));
+		assert!(either.iter().any(|polygon| polygon.contains(Point { x: 300.0, y: 250.0 })));
+
+	}
+
+	#[test]
+	fn demo_coverage()
+	{
+
+		let regions = cameras();
+
+		//Summing the areas seen by at least one, two and three cameras counts each point once per camera seeing it
+		let layered: f32 = (1..4).map(|k| total_area(&coverage(&regions, k))).sum();
+		let expected: f32 = regions.iter().map(|triangles| fan_area(triangles)).sum();
+
+		assert!((layered - expected).abs() / expected < 0.001);
+		assert!((total_area(&coverage(&regions, 3)) - total_area(&intersection(&regions))).abs() < FLOATING_POINT_ERROR);
+		assert!(coverage(&regions, 4).is_empty());
+
+		//Points seen by one or two cameras are those seen by at least one, less those seen by all three
+		let partial = total_area(&coverage_between(&regions, 1, 2));
+		assert!((partial - (total_area(&union(&regions)) - total_area(&intersection(&regions)))).abs() / partial < 0.001);
+
+	}
+
+	#[test]
+	fn many_crossing_cones()
+	{
+
+		//Wide cones scattered over the demo world, whose edges cross each other hundreds of times
+		let walls = demo_walls();
+		let mut rng = StdRng::seed_from_u64(30);
+
+		let regions: Vec<Vec<Triangle>> = (0..12).map(|_|
+		{
+
+			let location = Point { x: rng.gen_range(10.0..700.0), y: rng.gen_range(10.0..500.0) };
+			let facing: f32 = rng.gen_range(0.0..std::f32::consts::PI * 2.0);
+
+			return generate_line_of_sight(location, Point { x: (facing + 1.0).cos(), y: (facing + 1.0).sin() }, Point { x: (facing - 1.0).cos(), y: (facing - 1.0).sin() }, &walls, ALL_LAYERS);
+
+		}).collect();
+
+		let layered: f32 = (1..=regions.len()).map(|k| total_area(&coverage(&regions, k))).sum();
+		let expected: f32 = regions.iter().map(|triangles| fan_area(triangles)).sum();
+		assert!((layered - expected).abs() / expected < 0.001);
+
+		//Union merges halves on their own before merging them together, which should give the same area as sweeping them all at once
+		let merged = union(&regions);
+		let swept = total_area(&coverage(&regions, 1));
+		assert!((total_area(&merged) - swept).abs() / swept < 0.001);
+		assert!(merged.iter().all(|polygon| polygon.signed_area() > -FLOATING_POINT_ERROR));
+
+	}
+
+}
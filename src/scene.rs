@@ -0,0 +1,427 @@
+use super::shadow;
+use super::sight;
+use super::space::{Point, Polygon, Rectangle, Segment, Triangle};
+
+//Identifies a segment in a scene. Handles stay valid until their segment is removed, and are never reused afterwards
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SegmentHandle
+{
+
+	index: u32,
+	generation: u32
+
+}
+
+struct Slot
+{
+
+	generation: u32,
+	dense: Option<usize>, //Where the slot's segment lives in the packed list, if it is occupied
+	door: bool
+
+}
+
+//Owns a set of segments which can be added, removed and moved between frames
+//The segments are kept packed together so that queries run over a plain list without copying it each frame
+pub struct Scene
+{
+
+	segments: Vec<Segment>,
+	owners: Vec<usize>, //The slot owning each packed segment
+	slots: Vec<Slot>,
+	free: Vec<usize>
+
+}
+
+impl Scene
+{
+
+	pub fn new() -> Scene
+	{
+
+		return Scene { segments: Vec::new(), owners: Vec::new(), slots: Vec::new(), free: Vec::new() };
+
+	}
+
+	fn dense(&self, handle: SegmentHandle) -> Option<usize>
+	{
+
+		let slot = self.slots.get(handle.index as usize)?;
+		if slot.generation != handle.generation
+		{
+
+			return None;
+
+		}
+
+		return slot.dense;
+
+	}
+
+	pub fn insert(&mut self, segment: Segment) -> SegmentHandle
+	{
+
+		let index = match self.free.pop()
+		{
+
+			Some(index) => index,
+			None =>
+			{
+
+				self.slots.push(Slot { generation: 0, dense: None, door: false });
+				self.slots.len() - 1
+
+			}
+
+		};
+
+		self.slots[index].dense = Some(self.segments.len());
+		self.segments.push(segment);
+		self.owners.push(index);
+
+		return SegmentHandle { index: index as u32, generation: self.slots[index].generation };
+
+	}
+
+	//Returns the removed segment, or None if the handle was already removed
+	pub fn remove(&mut self, handle: SegmentHandle) -> Option<Segment>
+	{
+
+		let dense = self.dense(handle)?;
+
+		//The last packed segment moves into the gap
+		let segment = self.segments.swap_remove(dense);
+		self.owners.swap_remove(dense);
+
+		if dense < self.owners.len()
+		{
+
+			self.slots[self.owners[dense]].dense = Some(dense);
+
+		}
+
+		let slot = &mut self.slots[handle.index as usize];
+		slot.dense = None;
+		slot.door = false;
+		slot.generation += 1;
+
+		self.free.push(handle.index as usize);
+
+		return Some(segment);
+
+	}
+
+	//Doors are ordinary segments which boundary_doors reports on
+	pub fn insert_door(&mut self, segment: Segment) -> SegmentHandle
+	{
+
+		let handle = self.insert(segment);
+		self.slots[handle.index as usize].door = true;
+
+		return handle;
+
+	}
+
+	pub fn is_door(&self, handle: SegmentHandle) -> bool
+	{
+
+		return self.dense(handle).is_some() && self.slots[handle.index as usize].door;
+
+	}
+
+	//Disabled segments stay in the scene but stop occluding, e.g. a door is closed while enabled and open while disabled
+	//Returns false if the handle was already removed
+	pub fn set_enabled(&mut self, handle: SegmentHandle, enabled: bool) -> bool
+	{
+
+		return match self.get_mut(handle)
+		{
+
+			Some(segment) =>
+			{
+
+				segment.enabled = enabled;
+				true
+
+			},
+			None => false
+
+		};
+
+	}
+
+	pub fn get(&self, handle: SegmentHandle) -> Option<&Segment>
+	{
+
+		return self.dense(handle).map(|dense| &self.segments[dense]);
+
+	}
+
+	pub fn get_mut(&mut self, handle: SegmentHandle) -> Option<&mut Segment>
+	{
+
+		let dense = self.dense(handle)?;
+
+		return Some(&mut self.segments[dense]);
+
+	}
+
+	//Moves a segment to new endpoints, keeping its layers and whether it is enabled. Returns false if the handle was already removed
+	pub fn move_segment(&mut self, handle: SegmentHandle, start: Point, end: Point) -> bool
+	{
+
+		return match self.get_mut(handle)
+		{
+
+			Some(segment) =>
+			{
+
+				*segment = Segment { enabled: segment.enabled, ..Segment::with_layers(start, end, segment.layers) };
+				true
+
+			},
+			None => false
+
+		};
+
+	}
+
+	pub fn len(&self) -> usize
+	{
+
+		return self.segments.len();
+
+	}
+
+	pub fn is_empty(&self) -> bool
+	{
+
+		return self.segments.is_empty();
+
+	}
+
+	//The packed segments, for use with the free query functions. Their order changes whenever a segment is removed
+	pub fn segments(&self) -> &[Segment]
+	{
+
+		return &self.segments;
+
+	}
+
+	//Converts an index into segments(), such as Triangle::wall, into a handle
+	pub fn handle_of(&self, index: usize) -> SegmentHandle
+	{
+
+		let slot = self.owners[index];
+
+		return SegmentHandle { index: slot as u32, generation: self.slots[slot].generation };
+
+	}
+
+	//Each triangle's wall is an index into segments(), which handle_of converts into a handle
+	pub fn line_of_sight(&self, location: Point, upper: Point, lower: Point, mask: u32) -> Vec<Triangle>
+	{
+
+		return sight::generate_line_of_sight(location, upper, lower, &self.segments, mask);
+
+	}
+
+	pub fn team_line_of_sight(&self, viewers: &[(Point, Point, Point)], mask: u32) -> Vec<Polygon>
+	{
+
+		return sight::generate_team_line_of_sight(viewers, &self.segments, mask);
+
+	}
+
+	pub fn visible_walls(&self, location: Point, upper: Point, lower: Point, mask: u32) -> Vec<(SegmentHandle, f32, f32)>
+	{
+
+		return sight::generate_visible_walls(location, upper, lower, &self.segments, mask).into_iter().map(|(index, start, end)| (self.handle_of(index), start, end)).collect();
+
+	}
+
+	//Returns the closed doors forming part of the edge of the line of sight, i.e. the doors which would reveal more if opened
+	pub fn boundary_doors(&self, location: Point, upper: Point, lower: Point, mask: u32) -> Vec<SegmentHandle>
+	{
+
+		let mut doors: Vec<SegmentHandle> = Vec::new();
+
+		for triangle in self.line_of_sight(location, upper, lower, mask).iter()
+		{
+
+			if let Some(index) = triangle.wall
+			{
+
+				let handle = self.handle_of(index);
+
+				if self.slots[handle.index as usize].door && !doors.contains(&handle)
+				{
+
+					doors.push(handle);
+
+				}
+
+			}
+
+		}
+
+		return doors;
+
+	}
+
+	pub fn raycast(&self, location: Point, ray: Point, mask: u32) -> Option<f32>
+	{
+
+		return sight::raycast(location, ray, &self.segments, mask);
+
+	}
+
+	pub fn is_visible(&self, location: Point, target: Point, mask: u32) -> bool
+	{
+
+		return sight::is_visible(location, target, &self.segments, mask);
+
+	}
+
+	pub fn shadows(&self, location: Point, upper: Point, lower: Point, mask: u32, bounds: &Rectangle) -> Vec<Polygon>
+	{
+
+		return shadow::generate_shadows(location, upper, lower, &self.segments, mask, bounds);
+
+	}
+
+	pub fn shadow_quads(&self, location: Point, mask: u32, distance: f32) -> Vec<Polygon>
+	{
+
+		return shadow::generate_shadow_quads(location, &self.segments, mask, distance);
+
+	}
+
+}
+
+impl Default for Scene
+{
+
+	fn default() -> Scene
+	{
+
+		return Scene::new();
+
+	}
+
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::space::ALL_LAYERS;
+
+	fn wall(x: f32) -> Segment
+	{
+
+		return Segment::new(Point { x, y: -1.0 }, Point { x, y: 1.0 });
+
+	}
+
+	#[test]
+	fn handles()
+	{
+
+		let mut scene = Scene::new();
+
+		let first = scene.insert(wall(1.0));
+		let second = scene.insert(wall(2.0));
+		let third = scene.insert(wall(3.0));
+
+		assert_eq!(Some(wall(1.0)), scene.remove(first));
+		assert_eq!(None, scene.remove(first));
+		assert_eq!(None, scene.get(first));
+
+		//The freed slot is reused without reviving the old handle
+		let fourth = scene.insert(wall(4.0));
+		assert_ne!(first, fourth);
+		assert_eq!(None, scene.get(first));
+
+		assert_eq!(3, scene.len());
+		assert_eq!(Some(&wall(2.0)), scene.get(second));
+		assert_eq!(Some(&wall(3.0)), scene.get(third));
+		assert_eq!(Some(&wall(4.0)), scene.get(fourth));
+
+		for index in 0..scene.len()
+		{
+
+			assert_eq!(Some(&scene.segments()[index]), scene.get(scene.handle_of(index)));
+
+		}
+
+	}
+
+	#[test]
+	fn dynamic_occluders()
+	{
+
+		let mut scene = Scene::new();
+		let location = Point { x: 0.0, y: 0.0 };
+		let target = Point { x: 5.0, y: 0.0 };
+
+		let door = scene.insert(wall(2.0));
+		let far = scene.insert(wall(10.0));
+		assert!(!scene.is_visible(location, target, ALL_LAYERS));
+
+		//Swing the door open
+		assert!(scene.move_segment(door, Point { x: 2.0, y: 1.0 }, Point { x: 4.0, y: 1.0 }));
+		assert!(scene.is_visible(location, target, ALL_LAYERS));
+		assert_eq!(Some(10.0), scene.raycast(location, Point { x: 1.0, y: 0.0 }, ALL_LAYERS));
+
+		scene.remove(far);
+		assert_eq!(None, scene.raycast(location, Point { x: 1.0, y: 0.0 }, ALL_LAYERS));
+
+		let walls = scene.visible_walls(location, Point { x: 1.0, y: 1.0 }, Point { x: 1.0, y: -0.5 }, ALL_LAYERS);
+		assert!(!walls.is_empty());
+		assert!(walls.iter().all(|portion| portion.0 == door));
+
+	}
+
+	#[test]
+	fn doors()
+	{
+
+		let mut scene = Scene::new();
+		let location = Point { x: 0.0, y: 0.0 };
+		let upper = Point { x: 1.0, y: 1.0 };
+		let lower = Point { x: 1.0, y: -1.0 };
+
+		let front = scene.insert_door(wall(2.0));
+		let back = scene.insert_door(wall(4.0));
+		let far = scene.insert(Segment::new(Point { x: 10.0, y: -20.0 }, Point { x: 10.0, y: 20.0 }));
+
+		assert!(scene.is_door(front));
+		assert!(!scene.is_door(far));
+		assert_eq!(vec![front], scene.boundary_doors(location, upper, lower, ALL_LAYERS));
+
+		//Opening the front door reveals the one behind it
+		assert!(scene.set_enabled(front, false));
+		assert!(!scene.is_visible(location, Point { x: 5.0, y: 0.0 }, ALL_LAYERS));
+		assert!(scene.is_visible(location, Point { x: 3.0, y: 0.0 }, ALL_LAYERS));
+		assert_eq!(vec![back], scene.boundary_doors(location, upper, lower, ALL_LAYERS));
+
+		scene.set_enabled(back, false);
+		assert!(scene.boundary_doors(location, upper, lower, ALL_LAYERS).is_empty());
+
+		//Sliding an open door along does not close it
+		assert!(scene.move_segment(front, Point { x: 2.5, y: -1.0 }, Point { x: 2.5, y: 1.0 }));
+		assert!(!scene.get(front).unwrap().enabled);
+		assert!(scene.is_visible(location, Point { x: 5.0, y: 0.0 }, ALL_LAYERS));
+
+		scene.set_enabled(front, true);
+		assert_eq!(vec![front], scene.boundary_doors(location, upper, lower, ALL_LAYERS));
+
+		scene.remove(front);
+		assert!(!scene.is_door(front));
+		assert!(!scene.set_enabled(front, true));
+
+	}
+
+}
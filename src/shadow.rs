@@ -0,0 +1,215 @@
+use super::space::{Point, Polygon, Rectangle, Segment, FLOATING_POINT_ERROR};
+use super::sight::generate_line_of_sight;
+
+//Appends the corners of bounds met when sweeping counterclockwise from edge first to edge last
+//If both edges are the same, wrap determines whether the sweep goes all the way around
+fn push_corners(bounds: &Rectangle, first: usize, last: usize, wrap: bool, vertices: &mut Vec<Point>)
+{
+
+	let corners = bounds.corners();
+
+	let mut count = (last + 4 - first) % 4;
+	if count == 0 && wrap
+	{
+
+		count = 4;
+
+	}
+
+	for i in 1..count + 1
+	{
+
+		vertices.push(corners[(first + i) % 4]);
+
+	}
+
+}
+
+//Builds the region of bounds lying behind near_lower and near_upper as seen from location, in counterclockwise order
+//near_upper must be counterclockwise from near_lower by less than pi
+fn shadow_behind(location: Point, near_lower: Point, near_upper: Point, bounds: &Rectangle) -> Polygon
+{
+
+	let (exit_lower, edge_lower) = bounds.exit(location, near_lower - location);
+	let (exit_upper, edge_upper) = bounds.exit(location, near_upper - location);
+
+	let mut vertices = vec![near_lower, exit_lower];
+	push_corners(bounds, edge_lower, edge_upper, false, &mut vertices);
+	vertices.push(exit_upper);
+	vertices.push(near_upper);
+
+	return Polygon::new(vertices);
+
+}
+
+//Returns the parts of bounds which cannot be seen from location, i.e. the complement of generate_line_of_sight, as counterclockwise polygons
+//Everything outside the cone between lower and upper is returned as a single polygon. Location must lie inside bounds
+pub fn generate_shadows<'a, I>(location: Point, upper: Point, lower: Point, segments: I, mask: u32, bounds: &Rectangle) -> Vec<Polygon> where I: IntoIterator<Item = &'a Segment>
+{
+
+	let edges = bounds.edges();
+
+	//Close the scene off with the bounds so that every ray hits something
+	let mut walls: Vec<&Segment> = segments.into_iter().collect();
+	let count = walls.len();
+	walls.extend(edges.iter());
+
+	let mut shadows: Vec<Polygon> = Vec::new();
+
+	for triangle in generate_line_of_sight(location, upper, lower, walls.iter().copied(), mask).iter()
+	{
+
+		let near_lower = triangle.vertices[1];
+		let near_upper = triangle.vertices[2];
+
+		match triangle.wall
+		{
+
+			//Nothing but the bounds themselves stop this part of the view
+			Some(index) if index >= count => continue,
+			None => continue,
+			_ => ()
+
+		}
+
+		//Rays with the same direction only leave a line in shadow
+		let lower_ray = near_lower - location;
+		let upper_ray = near_upper - location;
+		if lower_ray.x * upper_ray.y - lower_ray.y * upper_ray.x < FLOATING_POINT_ERROR
+		{
+
+			continue;
+
+		}
+
+		shadows.push(shadow_behind(location, near_lower, near_upper, bounds));
+
+	}
+
+	//Everything outside of the cone is in shadow too
+	let (exit_upper, edge_upper) = bounds.exit(location, upper);
+	let (exit_lower, edge_lower) = bounds.exit(location, lower);
+
+	let mut outside = vec![location, exit_upper];
+	push_corners(bounds, edge_upper, edge_lower, true, &mut outside);
+	outside.push(exit_lower);
+
+	shadows.push(Polygon::new(outside));
+
+	return shadows;
+
+}
+
+//Returns, for each segment on the layers selected by mask, the counterclockwise quad it shadows when lit from location
+//The far edge of each quad is found by pushing the segment's endpoints distance further away from location
+pub fn generate_shadow_quads<'a, I>(location: Point, segments: I, mask: u32, distance: f32) -> Vec<Polygon> where I: IntoIterator<Item = &'a Segment>
+{
+
+	let mut quads: Vec<Polygon> = Vec::new();
+
+	for segment in segments
+	{
+
+		if !segment.occludes(mask)
+		{
+
+			continue;
+
+		}
+
+		let start_ray = segment.start - location;
+		let end_ray = segment.end - location;
+
+		//Order the endpoints so that the quad winds counterclockwise
+		let cross = start_ray.x * end_ray.y - start_ray.y * end_ray.x;
+		if cross.abs() < FLOATING_POINT_ERROR //The segment is seen edge on and casts no shadow
+		{
+
+			continue;
+
+		}
+
+		let (near_lower, lower_ray, near_upper, upper_ray) = if cross > 0.0
+		{
+
+			(segment.start, start_ray, segment.end, end_ray)
+
+		}
+		else
+		{
+
+			(segment.end, end_ray, segment.start, start_ray)
+
+		};
+
+		let far_lower = near_lower + lower_ray.scale(distance / lower_ray.dot(&lower_ray).sqrt());
+		let far_upper = near_upper + upper_ray.scale(distance / upper_ray.dot(&upper_ray).sqrt());
+
+		quads.push(Polygon::new(vec![near_lower, far_lower, far_upper, near_upper]));
+
+	}
+
+	return quads;
+
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::space::ALL_LAYERS;
+
+	#[test]
+	fn shadows_complement_sight()
+	{
+
+		let mut segments = Vec::new();
+		segments.push(Segment::new(Point { x: 6.0, y: 4.0 }, Point { x: 6.0, y: 6.0 }));
+		segments.push(Segment::new(Point { x: 8.0, y: 5.0 }, Point { x: 7.0, y: 8.0 }));
+
+		let bounds = Rectangle::new(Point { x: 0.0, y: 0.0 }, Point { x: 10.0, y: 10.0 });
+		let location = Point { x: 5.0, y: 5.0 };
+		let upper = Point { x: 0.0, y: 1.0 };
+		let lower = Point { x: 1.0, y: -1.0 };
+
+		let shadows = generate_shadows(location, upper, lower, &segments, ALL_LAYERS, &bounds);
+
+		let mut walls = segments.clone();
+		walls.extend(bounds.edges().iter());
+		let sight = generate_line_of_sight(location, upper, lower, &walls, ALL_LAYERS);
+
+		let shadow_area: f32 = shadows.iter().map(|shadow| shadow.signed_area()).sum();
+		let sight_area: f32 = sight.iter().map(|triangle| triangle.area()).sum();
+
+		assert!(shadows.iter().all(|shadow| shadow.signed_area() >= 0.0));
+		assert!((shadow_area + sight_area - 100.0).abs() < 0.01);
+
+		//The region straight behind the first wall is in shadow
+		let behind = shadows.iter().any(|shadow| shadow.vertices.contains(&Point { x: 6.0, y: 5.0 }) && shadow.vertices.contains(&Point { x: 10.0, y: 5.0 }));
+		assert!(behind);
+
+	}
+
+	#[test]
+	fn shadow_quads()
+	{
+
+		let mut segments = Vec::new();
+		segments.push(Segment::new(Point { x: 2.0, y: 1.0 }, Point { x: 2.0, y: -1.0 }));
+		segments.push(Segment::new(Point { x: 1.0, y: 0.0 }, Point { x: 3.0, y: 0.0 }));
+		segments.push(Segment::with_layers(Point { x: -2.0, y: 1.0 }, Point { x: -2.0, y: -1.0 }, 0b10));
+
+		let quads = generate_shadow_quads(Point { x: 0.0, y: 0.0 }, &segments, 0b01, 4.0);
+
+		assert_eq!(1, quads.len());
+		assert!(quads[0].signed_area() > 0.0);
+		assert_eq!(Point { x: 2.0, y: -1.0 }, quads[0].vertices[0]);
+		assert_eq!(Point { x: 2.0, y: 1.0 }, quads[0].vertices[3]);
+
+		let far = quads[0].vertices[1] - quads[0].vertices[0];
+		assert!((far.dot(&far) - 16.0).abs() < FLOATING_POINT_ERROR);
+
+	}
+
+}
@@ -1,74 +1,872 @@
-use super::space::{Point, Segment, Triangle};
+use super::region;
+use super::space::{Occluder, OccluderSource, Point, Polygon, Segment, Triangle, FLOATING_POINT_ERROR};
 
-pub fn generate_line_of_sight(location: Point, upper: Point, lower: Point, segments: &Vec<Segment>) -> Vec<Triangle>
+//A way of computing line of sight, so that different approaches can be swapped for one another and checked against each other
+//Implementations replace the contents of output with triangles fanning counterclockwise from lower to upper around location,
+//each recording the index in occluders of the wall forming its far edge
+pub trait VisibilityAlgorithm
 {
 
-    // ANCHOR: section1
-    let mut rays: Vec<Point> = Vec::new();
-	rays.push(lower);
-	rays.push(upper);
+	fn compute_into<'a, I>(&mut self, location: Point, upper: Point, lower: Point, occluders: I, mask: u32, output: &mut Vec<Triangle>) where I: OccluderSource<'a>;
 
-	//Collect the rays we need to project
-	for segment in segments.iter()
+}
+
+//Scratch space for computing line of sight, reused between calls so that steady state frames do not allocate
+pub struct VisibilityContext
+{
+
+	rays: Vec<Point>
+
+}
+
+impl VisibilityContext
+{
+
+	pub fn new() -> VisibilityContext
+	{
+
+		return VisibilityContext { rays: Vec::new() };
+
+	}
+
+	//Replaces the contents of output with the line of sight, reusing the capacity of both output and the context
+	//Only occluders on at least one of the layers selected by mask obstruct the view
+	//Each triangle records the index in occluders of the wall forming its far edge
+	pub fn compute_into<'a, I>(&mut self, location: Point, upper: Point, lower: Point, occluders: I, mask: u32, output: &mut Vec<Triangle>) where I: OccluderSource<'a>
 	{
 
-		let ray = segment.start - location;
+		let occluders = occluders.into_occluders();
+
+	    // ANCHOR: section1
+	    let rays = &mut self.rays;
+		rays.clear();
+		rays.push(lower);
+		rays.push(upper);
 
-		if ray.ray_between(lower, upper)
+		//Collect the rays we need to project
+		for occluder in occluders.clone()
 		{
 
-			rays.push(ray);
+			if !occluder.occludes(mask)
+			{
+
+				continue;
+
+			}
+
+			occluder.silhouette(location, &mut |ray|
+			{
+
+				if ray.ray_between(lower, upper)
+				{
+
+					rays.push(ray);
+
+				}
+
+			});
 
 		}
+	    // ANCHOR_END: section1
+
+	    // ANCHOR: sort
+	    //Sort the rays from lower to upper
+		Point::sort_from_angle(rays, lower);
+	    // ANCHOR_END: sort
+	    //
+	    // ANCHOR: final
+		//Actually create the triangles
+		let line_of_sight = output;
+		line_of_sight.clear();
 
-        let ray = segment.end - location;
+		for i in 0..rays.len()-1
+		{
+
+			let mut shortest_current = 0.0;
+			let mut shortest_next = 0.0;
+			let mut wall: Option<usize> = None;
+
+			for (index, occluder) in occluders.clone().enumerate()
+			{
+
+				if !occluder.occludes(mask)
+				{
+
+					continue;
+
+				}
+
+				let cast_current = occluder.raycast(location, rays[i]);
+				let cast_next = occluder.raycast(location, rays[i + 1]);
+
+				if cast_current.is_none() || cast_next.is_none()
+				{
+
+					continue;
+
+				}
+
+				let cast_current = cast_current.unwrap();
+				let cast_next = cast_next.unwrap();
+
+				//Walls meeting at the point the current ray passes through are tied, so the one closer along the next ray is in front
+				let tied = (cast_current - shortest_current).abs() < FLOATING_POINT_ERROR;
 
-        if ray.ray_between(lower, upper)
-        {
+				if shortest_current == 0.0 || (cast_current < shortest_current && !tied) || (tied && cast_next < shortest_next)
+				{
 
-            rays.push(ray);
+					shortest_current = cast_current;
+					shortest_next = cast_next;
+					wall = Some(index);
 
-        }
+				}
+
+			}
+
+			line_of_sight.push(Triangle::with_wall(location, location + rays[i].scale(shortest_current), location + rays[i + 1].scale(shortest_next), wall));
+
+		}
+	    // ANCHOR_END: final
 
 	}
-    // ANCHOR_END: section1
 
-    // ANCHOR: sort
-    //Sort the rays from lower to upper
-	Point::sort_from_angle(&mut rays, lower);
-    // ANCHOR_END: sort
-    //
-    // ANCHOR: final
-	//Actually create the triangles
+}
+
+//The reference algorithm, which sorts rays without any trigonometry
+impl VisibilityAlgorithm for VisibilityContext
+{
+
+	fn compute_into<'a, I>(&mut self, location: Point, upper: Point, lower: Point, occluders: I, mask: u32, output: &mut Vec<Triangle>) where I: OccluderSource<'a>
+	{
+
+		VisibilityContext::compute_into(self, location, upper, lower, occluders, mask, output);
+
+	}
+
+}
+
+impl Default for VisibilityContext
+{
+
+	fn default() -> VisibilityContext
+	{
+
+		return VisibilityContext::new();
+
+	}
+
+}
+
+//As VisibilityContext::compute_into, but with fresh buffers for callers which do not keep a context around
+pub fn generate_line_of_sight<'a, I>(location: Point, upper: Point, lower: Point, occluders: I, mask: u32) -> Vec<Triangle> where I: OccluderSource<'a>
+{
+
 	let mut line_of_sight: Vec<Triangle> = Vec::new();
+	VisibilityContext::new().compute_into(location, upper, lower, occluders, mask, &mut line_of_sight);
+
+	return line_of_sight;
+
+}
+
+//Line of sight in every direction, built from four quarter turn cones as each cone must be narrower than pi
+pub fn generate_full_line_of_sight<'a, I>(location: Point, occluders: I, mask: u32) -> Vec<Triangle> where I: OccluderSource<'a>
+{
+
+	let occluders = occluders.into_occluders();
+	let directions = [Point { x: 1.0, y: 0.0 }, Point { x: 0.0, y: 1.0 }, Point { x: -1.0, y: 0.0 }, Point { x: 0.0, y: -1.0 }];
+
+	let mut context = VisibilityContext::new();
+	let mut line_of_sight: Vec<Triangle> = Vec::new();
+	let mut quarter: Vec<Triangle> = Vec::new();
+
+	for i in 0..4
+	{
+
+		context.compute_into(location, directions[(i + 1) % 4], directions[i], occluders.clone(), mask, &mut quarter);
+		line_of_sight.append(&mut quarter);
+
+	}
+
+	return line_of_sight;
+
+}
+
+//Returns the area seen by at least one of the (location, upper, lower) viewers as non-overlapping polygons
+pub fn generate_team_line_of_sight<'a, I>(viewers: &[(Point, Point, Point)], occluders: I, mask: u32) -> Vec<Polygon> where I: OccluderSource<'a>
+{
+
+	let occluders = occluders.into_occluders();
+	let regions: Vec<Vec<Triangle>> = viewers.iter().map(|&(location, upper, lower)| generate_line_of_sight(location, upper, lower, occluders.clone(), mask)).collect();
+
+	return region::union(&regions);
+
+}
+
+//Returns the area seen all around from at least one of the points, as non-overlapping polygons
+fn weak_visibility<'a, I>(points: &[Point], occluders: I, mask: u32) -> Vec<Polygon> where I: OccluderSource<'a>
+{
+
+	let occluders = occluders.into_occluders();
+	let regions: Vec<Vec<Triangle>> = points.iter().map(|&location| generate_full_line_of_sight(location, occluders.clone(), mask)).collect();
+
+	return region::union(&regions);
+
+}
+
+//Returns the area visible from at least one point of source, such as a window or a doorway, as non-overlapping polygons
+//Source is sampled at samples evenly spaced points, so thin slivers seen only between samples may be missed
+//Source must not be one of the occluders, as every sample would then be hidden behind it
+pub fn generate_weak_visibility<'a, I>(source: &Segment, samples: usize, occluders: I, mask: u32) -> Vec<Polygon> where I: OccluderSource<'a>
+{
+
+	if samples == 0
+	{
+
+		panic!("Cannot sample segment at zero points.");
+
+	}
+
+	//Keep away from the endpoints, which may be shared with occluders
+	let points: Vec<Point> = (0..samples).map(|i| source.start + (source.end - source.start).scale((i as f32 + 0.5) / samples as f32)).collect();
+
+	return weak_visibility(&points, occluders, mask);
+
+}
+
+//Returns the area visible from at least one point on the boundary of source as non-overlapping polygons
+//Each edge is sampled at samples_per_edge evenly spaced points, as in generate_weak_visibility
+pub fn generate_polygon_weak_visibility<'a, I>(source: &Polygon, samples_per_edge: usize, occluders: I, mask: u32) -> Vec<Polygon> where I: OccluderSource<'a>
+{
+
+	if samples_per_edge == 0
+	{
+
+		panic!("Cannot sample polygon edges at zero points.");
+
+	}
+
+	let mut points: Vec<Point> = Vec::new();
+
+	for i in 0..source.vertices.len()
+	{
+
+		let start = source.vertices[i];
+		let edge = source.vertices[(i + 1) % source.vertices.len()] - start;
+
+		points.extend((0..samples_per_edge).map(|j| start + edge.scale((j as f32 + 0.5) / samples_per_edge as f32)));
+
+	}
+
+	return weak_visibility(&points, occluders, mask);
+
+}
+
+//Returns the portions of each segment visible from location as (segment index, s_start, s_end), where s is the segment's own parameter
+//Portions are sorted by segment index, then by s_start, and adjacent portions of the same segment are merged
+pub fn generate_visible_walls<'a, I>(location: Point, upper: Point, lower: Point, segments: I, mask: u32) -> Vec<(usize, f32, f32)> where I: IntoIterator<Item = &'a Segment>
+{
+
+	//Walls are looked up by index afterwards, so gather them into something indexable
+	let segments: Vec<&Segment> = segments.into_iter().collect();
+	let mut portions: Vec<(usize, f32, f32)> = Vec::new();
+
+	for triangle in generate_line_of_sight(location, upper, lower, segments.iter().copied(), mask).iter()
+	{
+
+		if let Some(index) = triangle.wall
+		{
+
+			let segment = segments[index];
+			let first = segment.parameter(triangle.vertices[1]).clamp(0.0, 1.0);
+			let second = segment.parameter(triangle.vertices[2]).clamp(0.0, 1.0);
+
+			//Triangles between rays with the same direction see only a single point of the wall
+			if (second - first).abs() > FLOATING_POINT_ERROR
+			{
+
+				portions.push((index, first.min(second), first.max(second)));
+
+			}
+
+		}
+
+	}
+
+	portions.sort_unstable_by(|a, b| a.0.cmp(&b.0).then(a.1.partial_cmp(&b.1).unwrap()));
+
+	let mut merged: Vec<(usize, f32, f32)> = Vec::new();
+	for portion in portions.into_iter()
+	{
+
+		if let Some(last) = merged.last_mut()
+		{
+
+			if last.0 == portion.0 && portion.1 <= last.2 + FLOATING_POINT_ERROR
+			{
+
+				last.2 = last.2.max(portion.2);
+				continue;
+
+			}
+
+		}
+
+		merged.push(portion);
+
+	}
+
+	return merged;
+
+}
+
+//Returns the parameter along ray of the closest occluder on the layers selected by mask, or None if the ray hits nothing
+pub fn raycast<'a, O, I>(location: Point, ray: Point, occluders: I, mask: u32) -> Option<f32> where O: Occluder + ?Sized + 'a, I: IntoIterator<Item = &'a O>
+{
+
+	let mut closest: Option<f32> = None;
+
+	for occluder in occluders
+	{
+
+		if !occluder.occludes(mask)
+		{
+
+			continue;
+
+		}
+
+		if let Some(cast) = occluder.raycast(location, ray)
+		{
+
+			if closest.is_none_or(|shortest| cast < shortest)
+			{
+
+				closest = Some(cast);
+
+			}
+
+		}
+
+	}
+
+	return closest;
+
+}
+
+//Returns true if no occluder on the layers selected by mask lies strictly between location and target
+pub fn is_visible<'a, O, I>(location: Point, target: Point, occluders: I, mask: u32) -> bool where O: Occluder + ?Sized + 'a, I: IntoIterator<Item = &'a O>
+{
+
+	let ray = target - location;
+	if ray.x == 0.0 && ray.y == 0.0
+	{
+
+		return true;
+
+	}
+
+	return match raycast(location, ray, occluders, mask)
+	{
+
+		Some(cast) => cast >= 1.0 - FLOATING_POINT_ERROR,
+		None => true
+
+	};
+
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::atan::AtanContext;
+	use crate::demo_walls;
+	use crate::space::ALL_LAYERS;
+
+	use rand::prelude::*;
+
+	use std::alloc::{GlobalAlloc, Layout, System};
+	use std::cell::Cell;
+
+	//Counts allocations made by each thread, so that tests running in parallel do not disturb each other
+	struct CountingAllocator;
+
+	thread_local!
+	{
+
+		static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+
+	}
 
-	for i in 0..rays.len()-1
+	unsafe impl GlobalAlloc for CountingAllocator
 	{
 
-		let mut shortest_current = 0.0;
-		let mut shortest_next = 0.0;
+		unsafe fn alloc(&self, layout: Layout) -> *mut u8
+		{
+
+			let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+
+			return System.alloc(layout);
+
+		}
+
+		unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout)
+		{
+
+			System.dealloc(ptr, layout);
+
+		}
+
+	}
+
+	#[global_allocator]
+	static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+	const SIGHT: u32 = 0b01;
+	const MOVEMENT: u32 = 0b10;
+
+	fn fence_and_smoke() -> Vec<Segment>
+	{
+
+		let mut segments = Vec::new();
+		segments.push(Segment::with_layers(Point { x: 2.0, y: -1.0 }, Point { x: 2.0, y: 1.0 }, MOVEMENT));
+		segments.push(Segment::with_layers(Point { x: 4.0, y: -1.0 }, Point { x: 4.0, y: 1.0 }, SIGHT));
+		segments.push(Segment::new(Point { x: 6.0, y: -1.0 }, Point { x: 6.0, y: 1.0 }));
+
+		return segments;
+
+	}
+
+	#[test]
+	fn masked_raycast()
+	{
+
+		let segments = fence_and_smoke();
+		let location = Point { x: 0.0, y: 0.0 };
+		let ray = Point { x: 1.0, y: 0.0 };
+
+		assert_eq!(Some(2.0), raycast(location, ray, &segments, MOVEMENT));
+		assert_eq!(Some(4.0), raycast(location, ray, &segments, SIGHT));
+		assert_eq!(Some(2.0), raycast(location, ray, &segments, SIGHT | MOVEMENT));
+		assert_eq!(None, raycast(location, ray, &segments, 0));
+
+	}
 
-		for segment in segments.iter()
+	#[test]
+	fn masked_visibility()
+	{
+
+		let segments = fence_and_smoke();
+		let location = Point { x: 0.0, y: 0.0 };
+
+		assert!(is_visible(location, Point { x: 3.0, y: 0.0 }, &segments, SIGHT));
+		assert!(!is_visible(location, Point { x: 3.0, y: 0.0 }, &segments, MOVEMENT));
+		assert!(!is_visible(location, Point { x: 5.0, y: 0.0 }, &segments, SIGHT));
+		assert!(is_visible(location, Point { x: 1.0, y: 0.0 }, &segments, MOVEMENT));
+		assert!(!is_visible(location, Point { x: 7.0, y: 0.0 }, &segments, 0b100));
+		assert!(is_visible(location, Point { x: 0.0, y: 5.0 }, &segments, SIGHT | MOVEMENT));
+
+	}
+
+	#[test]
+	fn masked_line_of_sight()
+	{
+
+		let segments = fence_and_smoke();
+		let location = Point { x: 0.0, y: 0.0 };
+		let upper = Point { x: 1.0, y: 0.1 };
+		let lower = Point { x: 1.0, y: -0.1 };
+
+		for triangle in generate_line_of_sight(location, upper, lower, &segments, SIGHT).iter()
+		{
+
+			assert!((triangle.vertices[1].x - 4.0).abs() < FLOATING_POINT_ERROR);
+			assert!((triangle.vertices[2].x - 4.0).abs() < FLOATING_POINT_ERROR);
+
+		}
+
+		for triangle in generate_line_of_sight(location, upper, lower, &segments, MOVEMENT).iter()
 		{
 
-			let cast_current = segment.raycast(location, rays[i]);
-			let cast_next = segment.raycast(location, rays[i + 1]);
+			assert!((triangle.vertices[1].x - 2.0).abs() < FLOATING_POINT_ERROR);
+			assert!((triangle.vertices[2].x - 2.0).abs() < FLOATING_POINT_ERROR);
+
+		}
+
+	}
+
+	#[test]
+	fn steady_state_does_not_allocate()
+	{
+
+		let walls = demo_walls();
+		let location = Point { x: 355.0, y: 255.0 };
+
+		let mut context = VisibilityContext::new();
+		let mut sight: Vec<Triangle> = Vec::new();
+
+		//The first frame sizes the buffers for the widest view
+		context.compute_into(location, Point { x: -1.0, y: 0.01 }, Point { x: 1.0, y: 0.0 }, &walls, ALL_LAYERS, &mut sight);
+
+		//Make sure the allocator is really counting
+		let before = ALLOCATIONS.with(|count| count.get());
+		let expected = generate_line_of_sight(location, Point { x: 0.0, y: 1.0 }, Point { x: 1.0, y: 0.0 }, &walls, ALL_LAYERS);
+		let after = ALLOCATIONS.with(|count| count.get());
+
+		assert!(after > before);
+
+		let before = ALLOCATIONS.with(|count| count.get());
+		context.compute_into(location, Point { x: 0.0, y: 1.0 }, Point { x: 1.0, y: 0.0 }, &walls, ALL_LAYERS, &mut sight);
+		let after = ALLOCATIONS.with(|count| count.get());
 
-			if cast_current.is_some() && cast_next.is_some() && (shortest_current == 0.0 || cast_current.unwrap() < shortest_current)
+		assert_eq!(before, after);
+		assert_eq!(expected.len(), sight.len());
+
+		for (triangle, expected) in sight.iter().zip(expected.iter())
+		{
+
+			assert_eq!(expected.vertices, triangle.vertices);
+			assert_eq!(expected.wall, triangle.wall);
+
+		}
+
+	}
+
+	#[test]
+	fn borrowed_segments()
+	{
+
+		//Segments stored inside larger structs can be borrowed without copying them out
+		struct Entity
+		{
+
+			wall: Segment,
+			_health: u32
+
+		}
+
+		let walls = fence_and_smoke();
+		let entities: Vec<Entity> = walls.iter().map(|&wall| Entity { wall, _health: 100 }).collect();
+
+		let location = Point { x: 0.0, y: 0.0 };
+		let upper = Point { x: 1.0, y: 0.1 };
+		let lower = Point { x: 1.0, y: -0.1 };
+
+		let from_vec = generate_line_of_sight(location, upper, lower, &walls, SIGHT);
+		let from_slice = generate_line_of_sight(location, upper, lower, &walls[1..], SIGHT);
+		let from_entities = generate_line_of_sight(location, upper, lower, entities.iter().map(|entity| &entity.wall), SIGHT);
+
+		assert_eq!(from_vec.len(), from_entities.len());
+		for (expected, triangle) in from_vec.iter().zip(from_entities.iter())
+		{
+
+			assert_eq!(expected.vertices, triangle.vertices);
+			assert_eq!(expected.wall, triangle.wall);
+
+		}
+
+		//Indices are relative to whatever was passed in
+		assert!(from_vec.iter().all(|triangle| triangle.wall == Some(1)));
+		assert!(from_slice.iter().all(|triangle| triangle.wall == Some(0)));
+
+		assert_eq!(Some(4.0), raycast(location, Point { x: 1.0, y: 0.0 }, entities.iter().map(|entity| &entity.wall), SIGHT));
+		assert!(!is_visible(location, Point { x: 5.0, y: 0.0 }, entities.iter().map(|entity| &entity.wall), SIGHT));
+
+	}
+
+	#[test]
+	fn far_edge_walls()
+	{
+
+		let mut segments = Vec::new();
+		segments.push(Segment::new(Point { x: 5.0, y: -2.0 }, Point { x: 5.0, y: 0.0 }));
+		segments.push(Segment::new(Point { x: 3.0, y: 1.0 }, Point { x: 3.0, y: 6.0 }));
+		segments.push(Segment::new(Point { x: 10.0, y: -20.0 }, Point { x: 10.0, y: 20.0 }));
+
+		let location = Point { x: 0.0, y: 0.0 };
+		let upper = Point { x: 1.0, y: 1.0 };
+		let lower = Point { x: 1.0, y: -1.0 };
+
+		let sight = generate_line_of_sight(location, upper, lower, &segments, ALL_LAYERS);
+		let walls: Vec<Option<usize>> = sight.iter().map(|triangle| triangle.wall).collect();
+
+		assert_eq!(vec![Some(2), Some(0), Some(2), Some(1)], walls);
+
+	}
+
+	//An axis aligned crate, blocking sight on every layer
+	struct Crate
+	{
+
+		min: Point,
+		max: Point
+
+	}
+
+	impl Crate
+	{
+
+		fn edges(&self) -> Vec<Segment>
+		{
+
+			let corners = [self.min, Point { x: self.max.x, y: self.min.y }, self.max, Point { x: self.min.x, y: self.max.y }];
+
+			return (0..4).map(|i| Segment::new(corners[i], corners[(i + 1) % 4])).collect();
+
+		}
+
+	}
+
+	impl Occluder for Crate
+	{
+
+		fn silhouette(&self, location: Point, candidate: &mut dyn FnMut(Point))
+		{
+
+			for edge in self.edges().iter()
 			{
 
-				shortest_current = cast_current.unwrap();
-				shortest_next = cast_next.unwrap();
+				candidate(edge.start - location);
+
+			}
+
+		}
+
+		fn raycast(&self, location: Point, ray: Point) -> Option<f32>
+		{
+
+			return raycast(location, ray, &self.edges(), ALL_LAYERS);
+
+		}
+
+		fn occludes(&self, _mask: u32) -> bool
+		{
+
+			return true;
+
+		}
+
+	}
+
+	#[test]
+	fn custom_occluders()
+	{
+
+		let location = Point { x: 0.0, y: 0.0 };
+		let upper = Point { x: 1.0, y: 1.0 };
+		let lower = Point { x: 1.0, y: -1.0 };
+
+		let crates = vec![Crate { min: Point { x: 2.0, y: -1.0 }, max: Point { x: 3.0, y: 1.0 } }, Crate { min: Point { x: 5.0, y: -5.0 }, max: Point { x: 6.0, y: 5.0 } }];
+		let sight = generate_line_of_sight(location, upper, lower, &crates, ALL_LAYERS);
+
+		//The near crate hides the middle of the far one
+		let area: f32 = sight.iter().map(|triangle| triangle.area()).sum();
+		assert!((area - 14.5).abs() < FLOATING_POINT_ERROR);
+		assert!(sight.iter().all(|triangle| triangle.wall.is_some()));
+		assert!(sight.iter().any(|triangle| triangle.wall == Some(0) && triangle.vertices[1] == Point { x: 2.0, y: -1.0 }));
+
+		assert_eq!(Some(2.0), raycast(location, Point { x: 1.0, y: 0.0 }, &crates, ALL_LAYERS));
+		assert!(!is_visible(location, Point { x: 4.0, y: 0.0 }, &crates, ALL_LAYERS));
+		assert!(is_visible(location, Point { x: 4.0, y: 3.0 }, &crates, ALL_LAYERS));
+
+		//Different kinds of occluder can be mixed behind trait objects
+		let mixed: Vec<Box<dyn Occluder>> = vec![Box::new(Segment::new(Point { x: 1.0, y: -1.0 }, Point { x: 1.0, y: 1.0 })), Box::new(Crate { min: Point { x: 2.0, y: 2.0 }, max: Point { x: 3.0, y: 3.0 } })];
+		assert!(!is_visible(location, Point { x: 4.0, y: 0.0 }, mixed.iter().map(|occluder| occluder.as_ref()), ALL_LAYERS));
+		assert!(!is_visible(location, Point { x: 4.0, y: 4.0 }, mixed.iter().map(|occluder| occluder.as_ref()), ALL_LAYERS));
+		assert!(is_visible(location, Point { x: 1.5, y: 4.0 }, mixed.iter().map(|occluder| occluder.as_ref()), ALL_LAYERS));
+
+	}
+
+	fn covered_area(triangles: &[Triangle]) -> f32
+	{
+
+		return triangles.iter().map(|triangle| triangle.area()).sum();
+
+	}
+
+	//Walls along with the (location, upper, lower) viewers looking at them
+	type Viewing = (Vec<Segment>, Vec<(Point, Point, Point)>);
+
+	//Runs a backend over a fixed set of scenes and checks that it covers the same area as the reference algorithm
+	fn check_backend<A: VisibilityAlgorithm>(backend: &mut A)
+	{
+
+		let mut scenes: Vec<Viewing> = Vec::new();
+
+		scenes.push((fence_and_smoke(), vec![(Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 1.0 }, Point { x: 1.0, y: -1.0 })]));
+
+		//Viewers scattered around the demo level with cones of random width
+		let mut rng = StdRng::seed_from_u64(26);
+		let mut viewers = vec![(Point { x: 150.0, y: 250.0 }, Point { x: 1.0, y: 1.0 }, Point { x: 1.0, y: -1.0 }), (Point { x: 650.0, y: 400.0 }, Point { x: -1.0, y: -1.0 }, Point { x: -1.0, y: 0.2 })];
+		for _ in 0..50
+		{
+
+			let location = Point { x: rng.gen_range(10.0..700.0), y: rng.gen_range(10.0..500.0) };
+			let facing: f32 = rng.gen_range(0.0..std::f32::consts::PI * 2.0);
+			let spread: f32 = rng.gen_range(0.05..1.5);
+
+			viewers.push((location, Point { x: (facing + spread).cos(), y: (facing + spread).sin() }, Point { x: (facing - spread).cos(), y: (facing - spread).sin() }));
+
+		}
+		scenes.push((demo_walls(), viewers));
+
+		let mut output: Vec<Triangle> = Vec::new();
+
+		for (segments, viewers) in scenes.iter()
+		{
+
+			for &(location, upper, lower) in viewers.iter()
+			{
+
+				let expected = covered_area(&generate_line_of_sight(location, upper, lower, segments, ALL_LAYERS));
+
+				backend.compute_into(location, upper, lower, segments, ALL_LAYERS, &mut output);
+				let area = covered_area(&output);
+
+				assert!((area - expected).abs() <= 0.001 * expected.max(1.0), "Covered {} instead of {} from {:?}", area, expected, location);
 
 			}
 
 		}
 
-		line_of_sight.push(Triangle::new(location, location + rays[i].scale(shortest_current), location + rays[i + 1].scale(shortest_next)));
+	}
+
+	#[test]
+	fn backends_agree()
+	{
+
+		check_backend(&mut VisibilityContext::new());
+		check_backend(&mut AtanContext::new());
+
+	}
+
+	#[test]
+	fn shared_endpoint()
+	{
+
+		//The floor comes first, but the post standing on it is what a ray grazing the post's foot hides behind
+		let mut walls = Vec::new();
+		walls.push(Segment::new(Point { x: 0.0, y: 0.0 }, Point { x: 4.0, y: 0.0 }));
+		walls.push(Segment::new(Point { x: 2.0, y: 0.0 }, Point { x: 2.0, y: 1.2 }));
+
+		let location = Point { x: 0.5, y: 1.5 };
+		let sight = generate_line_of_sight(location, Point { x: 1.0, y: 0.0 }, Point { x: 0.0, y: -1.0 }, &walls, ALL_LAYERS);
+
+		let hidden = Point { x: 3.0, y: 0.1 };
+		assert!(!is_visible(location, hidden, &walls, ALL_LAYERS));
+		assert!(sight.iter().all(|triangle| triangle.area() < FLOATING_POINT_ERROR || !triangle.contains(hidden)));
+
+	}
+
+	#[test]
+	fn full_line_of_sight()
+	{
+
+		let mut walls = Vec::new();
+		walls.push(Segment::new(Point { x: 0.0, y: 0.0 }, Point { x: 10.0, y: 0.0 }));
+		walls.push(Segment::new(Point { x: 10.0, y: 0.0 }, Point { x: 10.0, y: 10.0 }));
+		walls.push(Segment::new(Point { x: 10.0, y: 10.0 }, Point { x: 0.0, y: 10.0 }));
+		walls.push(Segment::new(Point { x: 0.0, y: 10.0 }, Point { x: 0.0, y: 0.0 }));
+		walls.push(Segment::new(Point { x: 6.0, y: 4.0 }, Point { x: 6.0, y: 6.0 }));
+
+		let sight = generate_full_line_of_sight(Point { x: 3.0, y: 5.0 }, &walls, ALL_LAYERS);
+		let area: f32 = sight.iter().map(|triangle| triangle.area()).sum();
+
+		//The wall's shadow is the trapezoid behind it, between x = 6 and x = 10
+		assert!((area - (100.0 - (2.0 + 14.0 / 3.0) * 0.5 * 4.0)).abs() < 0.01);
+		assert!(sight.iter().all(|triangle| triangle.wall.is_some()));
 
 	}
 
-    return line_of_sight;
-    // ANCHOR_END: final
+	#[test]
+	fn weak_visibility()
+	{
+
+		let mut walls = Vec::new();
+		walls.push(Segment::new(Point { x: 0.0, y: 0.0 }, Point { x: 10.0, y: 0.0 }));
+		walls.push(Segment::new(Point { x: 10.0, y: 0.0 }, Point { x: 10.0, y: 10.0 }));
+		walls.push(Segment::new(Point { x: 10.0, y: 10.0 }, Point { x: 0.0, y: 10.0 }));
+		walls.push(Segment::new(Point { x: 0.0, y: 10.0 }, Point { x: 0.0, y: 0.0 }));
+		walls.push(Segment::new(Point { x: 6.0, y: 5.0 }, Point { x: 4.0, y: 5.0 }));
+
+		let inside = |polygons: &Vec<Polygon>, point: Point| polygons.iter().any(|polygon| polygon.contains(point));
+
+		//A doorway along the bottom of the room sees round the table, except for the area just behind it
+		let doorway = Segment::new(Point { x: 2.0, y: 1.0 }, Point { x: 8.0, y: 1.0 });
+		let seen = generate_weak_visibility(&doorway, 9, &walls, ALL_LAYERS);
+
+		assert!(!is_visible(Point { x: 5.0, y: 1.0 }, Point { x: 5.0, y: 9.0 }, &walls, ALL_LAYERS));
+		assert!(inside(&seen, Point { x: 5.0, y: 9.0 }));
+		assert!(!inside(&seen, Point { x: 5.0, y: 5.5 }));
+		assert!(inside(&seen, Point { x: 1.0, y: 1.0 }));
+
+		let area: f32 = seen.iter().map(|polygon| polygon.area()).sum();
+		//Exactly, the area hidden from the whole doorway is a triangle of area 2, which sampling can only make larger
+		assert!(area > 97.0 && area < 98.0);
+
+		//Seen from a crate in the corner, the table hides the area just behind it but not the wall beyond
+		let crate_ = Polygon::new(vec![Point { x: 1.0, y: 1.0 }, Point { x: 2.0, y: 1.0 }, Point { x: 2.0, y: 2.0 }, Point { x: 1.0, y: 2.0 }]);
+		let seen = generate_polygon_weak_visibility(&crate_, 4, &walls, ALL_LAYERS);
+
+		assert!(inside(&seen, Point { x: 9.0, y: 2.0 }));
+		assert!(inside(&seen, Point { x: 5.0, y: 9.0 }));
+		assert!(!inside(&seen, Point { x: 5.5, y: 5.5 }));
+
+	}
+
+	#[test]
+	fn team_line_of_sight()
+	{
+
+		let mut segments = Vec::new();
+		segments.push(Segment::new(Point { x: 0.0, y: 0.0 }, Point { x: 10.0, y: 0.0 }));
+		segments.push(Segment::new(Point { x: 10.0, y: 0.0 }, Point { x: 10.0, y: 10.0 }));
+		segments.push(Segment::new(Point { x: 10.0, y: 10.0 }, Point { x: 0.0, y: 10.0 }));
+		segments.push(Segment::new(Point { x: 0.0, y: 10.0 }, Point { x: 0.0, y: 0.0 }));
+
+		//Two viewers facing each other, whose cones overlap in a diamond in the middle of the room
+		let viewers = [
+
+			(Point { x: 5.0, y: 1.0 }, Point { x: -1.0, y: 1.0 }, Point { x: 1.0, y: 1.0 }),
+			(Point { x: 5.0, y: 9.0 }, Point { x: 1.0, y: -1.0 }, Point { x: -1.0, y: -1.0 })
+
+		];
+
+		let polygons = generate_team_line_of_sight(&viewers, &segments, ALL_LAYERS);
+		let area: f32 = polygons.iter().map(|polygon| polygon.area()).sum();
+
+		assert!((area - (65.0 + 65.0 - 32.0)).abs() < 0.01);
+
+	}
+
+	#[test]
+	fn visible_walls()
+	{
+
+		let mut segments = Vec::new();
+		segments.push(Segment::new(Point { x: 5.0, y: -2.0 }, Point { x: 5.0, y: 0.0 }));
+		segments.push(Segment::new(Point { x: 3.0, y: 1.0 }, Point { x: 3.0, y: 6.0 }));
+		segments.push(Segment::new(Point { x: 10.0, y: -20.0 }, Point { x: 10.0, y: 20.0 }));
+		segments.push(Segment::new(Point { x: 12.0, y: -20.0 }, Point { x: 12.0, y: 20.0 }));
+
+		let location = Point { x: 0.0, y: 0.0 };
+		let upper = Point { x: 1.0, y: 1.0 };
+		let lower = Point { x: 1.0, y: -1.0 };
+
+		let portions = generate_visible_walls(location, upper, lower, &segments, ALL_LAYERS);
+		let expected = [(0, 0.0, 1.0), (1, 0.0, 0.4), (2, 0.25, 0.4), (2, 0.5, 0.5 + 10.0 / 3.0 / 40.0)];
+
+		assert_eq!(expected.len(), portions.len());
+		for (portion, expected) in portions.iter().zip(expected.iter())
+		{
+
+			assert_eq!(expected.0, portion.0);
+			assert!((expected.1 - portion.1).abs() < FLOATING_POINT_ERROR);
+			assert!((expected.2 - portion.2).abs() < FLOATING_POINT_ERROR);
+
+		}
+
+	}
 
 }
@@ -2,7 +2,7 @@ use std::ops::{Add, Sub};
 
 pub const FLOATING_POINT_ERROR: f32 = 0.0001;
 
-fn normalize_angle(angle: f32) -> f32
+pub fn normalize_angle(angle: f32) -> f32
 {
 
     if angle < 0.0
@@ -148,11 +148,17 @@ impl Sub for Point
 
 }
 
+//Layer mask with every bit set, used by segments which should block every kind of query
+pub const ALL_LAYERS: u32 = u32::MAX;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Segment
 {
 
 	pub start: Point,
-	pub end: Point
+	pub end: Point,
+	pub layers: u32,
+	pub enabled: bool //Disabled segments, such as open doors, never occlude
 
 }
 
@@ -160,6 +166,13 @@ impl Segment
 {
 
 	pub fn new(start: Point, end: Point) -> Segment
+	{
+
+		return Segment::with_layers(start, end, ALL_LAYERS);
+
+	}
+
+	pub fn with_layers(start: Point, end: Point, layers: u32) -> Segment
 	{
 
 		if start.x == end.x && start.y == end.y
@@ -169,7 +182,15 @@ impl Segment
 
 		}
 
-		return Segment { start, end };
+		return Segment { start, end, layers, enabled: true };
+
+	}
+
+	//Returns true if the segment is enabled and lies on at least one of the layers selected by mask
+	pub fn occludes(&self, mask: u32) -> bool
+	{
+
+		return self.enabled && self.layers & mask != 0;
 
 	}
 
@@ -230,13 +251,149 @@ impl Segment
 	}
     // ANCHOR_END: raycast
 
+	//Returns the parameter s for which start + s * (end - start) is the closest point on the segment's line to point
+	pub fn parameter(&self, point: Point) -> f32
+	{
+
+		let direction = self.end - self.start;
+
+		return (point - self.start).dot(&direction) / direction.dot(&direction);
+
+	}
+
+}
+
+//Anything which blocks line of sight. Occluders with curved edges should only be used where each triangle of the line of sight
+//can end on a straight edge, as the far edge of each triangle joins the hits of its two rays with a straight line
+pub trait Occluder
+{
+
+	//Calls candidate with the direction from location to every point where the occluder's outline may start or stop hiding what is behind it,
+	//e.g. the endpoints of a segment or the corners of a polygon
+	fn silhouette(&self, location: Point, candidate: &mut dyn FnMut(Point));
+
+	//Returns t such that location + t * ray is the closest point where the ray hits the occluder, if it does
+	fn raycast(&self, location: Point, ray: Point) -> Option<f32>;
+
+	//Returns true if the occluder obstructs queries on at least one of the layers selected by mask
+	fn occludes(&self, mask: u32) -> bool;
+
+}
+
+impl Occluder for Segment
+{
+
+	fn silhouette(&self, location: Point, candidate: &mut dyn FnMut(Point))
+	{
+
+		candidate(self.start - location);
+		candidate(self.end - location);
+
+	}
+
+	fn raycast(&self, location: Point, ray: Point) -> Option<f32>
+	{
+
+		return Segment::raycast(self, location, ray);
+
+	}
+
+	fn occludes(&self, mask: u32) -> bool
+	{
+
+		return Segment::occludes(self, mask);
+
+	}
+
 }
 
-#[derive(Debug)]
+//Where line of sight queries get their occluders from: anything which can be iterated over more than once to give borrowed occluders,
+//such as a slice, a Vec of references or a mapped iterator
+pub trait OccluderSource<'a>
+{
+
+	type Occluder: Occluder + ?Sized + 'a;
+	type Iter: Iterator<Item = &'a Self::Occluder> + Clone;
+
+	fn into_occluders(self) -> Self::Iter;
+
+}
+
+impl<'a, O, I> OccluderSource<'a> for I where O: Occluder + ?Sized + 'a, I: IntoIterator<Item = &'a O>, I::IntoIter: Clone
+{
+
+	type Occluder = O;
+	type Iter = I::IntoIter;
+
+	fn into_occluders(self) -> I::IntoIter
+	{
+
+		return self.into_iter();
+
+	}
+
+}
+
+//Either an occluder from the scene or one of the edges of a rectangle closing the scene off, as collected by Rectangle::close_off
+//The edges obstruct queries on every layer
+pub enum BoundedOccluder<'a, O: ?Sized>
+{
+
+	Occluder(&'a O),
+	Bound(Segment)
+
+}
+
+impl<'a, O> Occluder for BoundedOccluder<'a, O> where O: Occluder + ?Sized
+{
+
+	fn silhouette(&self, location: Point, candidate: &mut dyn FnMut(Point))
+	{
+
+		match self
+		{
+
+			BoundedOccluder::Occluder(occluder) => occluder.silhouette(location, candidate),
+			BoundedOccluder::Bound(edge) => edge.silhouette(location, candidate)
+
+		}
+
+	}
+
+	fn raycast(&self, location: Point, ray: Point) -> Option<f32>
+	{
+
+		return match self
+		{
+
+			BoundedOccluder::Occluder(occluder) => occluder.raycast(location, ray),
+			BoundedOccluder::Bound(edge) => edge.raycast(location, ray)
+
+		};
+
+	}
+
+	fn occludes(&self, mask: u32) -> bool
+	{
+
+		return match self
+		{
+
+			BoundedOccluder::Occluder(occluder) => occluder.occludes(mask),
+			BoundedOccluder::Bound(_) => true
+
+		};
+
+	}
+
+}
+
+#[derive(Clone, Debug)]
 pub struct Triangle
 {
 
-	pub vertices: [Point; 3]
+	pub vertices: [Point; 3],
+	pub wall: Option<usize> //Index of the segment forming the edge from vertices[1] to vertices[2], if any
 
 }
 
@@ -246,17 +403,552 @@ impl Triangle
 	pub fn new(a: Point, b: Point, c: Point) -> Triangle
 	{
 
-		return Triangle { vertices: [a, b, c] };
+		return Triangle::with_wall(a, b, c, None);
+
+	}
+
+	pub fn with_wall(a: Point, b: Point, c: Point, wall: Option<usize>) -> Triangle
+	{
+
+		return Triangle { vertices: [a, b, c], wall };
+
+	}
+
+	//Works for either winding. Points on an edge count as inside
+	//Triangles with no area contain nothing, as otherwise every point on the line through them would count
+	pub fn contains(&self, point: Point) -> bool
+	{
+
+		if self.area() < FLOATING_POINT_ERROR
+		{
+
+			return false;
+
+		}
+
+		let mut positive = false;
+		let mut negative = false;
+
+		for i in 0..3
+		{
+
+			let a = self.vertices[i];
+			let b = self.vertices[(i + 1) % 3];
+
+			let cross = (b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x);
+
+			if cross > FLOATING_POINT_ERROR
+			{
+
+				positive = true;
+
+			}
+			else if cross < -FLOATING_POINT_ERROR
+			{
+
+				negative = true;
+
+			}
+
+		}
+
+		return !(positive && negative);
+
+	}
+
+	pub fn area(&self) -> f32
+	{
+
+		let [a, b, c] = self.vertices;
+
+		return ((b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y)).abs() * 0.5;
+
+	}
+
+	pub fn centroid(&self) -> Point
+	{
+
+		let [a, b, c] = self.vertices;
+
+		return (a + b + c).scale(1.0 / 3.0);
 
 	}
 	
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon
+{
+
+	pub vertices: Vec<Point>
+
+}
+
+impl Polygon
+{
+
+	pub fn new(vertices: Vec<Point>) -> Polygon
+	{
+
+		return Polygon { vertices };
+
+	}
+
+	//Approximates a circle by a regular polygon with the given number of sides, wound counterclockwise
+	pub fn circle(center: Point, radius: f32, sides: usize) -> Polygon
+	{
+
+		if sides < 3
+		{
+
+			panic!("Cannot approximate circle with fewer than three sides.");
+
+		}
+
+		let step = 2.0 * std::f32::consts::PI / sides as f32;
+
+		return Polygon::new((0..sides).map(|i| center + Point { x: (step * i as f32).cos(), y: (step * i as f32).sin() }.scale(radius)).collect());
+
+	}
+
+	//Positive for counterclockwise vertices and negative for clockwise ones
+	pub fn signed_area(&self) -> f32
+	{
+
+		let mut twice_area = 0.0;
+
+		for i in 0..self.vertices.len()
+		{
+
+			let current = self.vertices[i];
+			let next = self.vertices[(i + 1) % self.vertices.len()];
+
+			twice_area += current.x * next.y - next.x * current.y;
+
+		}
+
+		return twice_area * 0.5;
+
+	}
+
+	pub fn area(&self) -> f32
+	{
+
+		return self.signed_area().abs();
+
+	}
+
+	pub fn perimeter(&self) -> f32
+	{
+
+		let mut perimeter = 0.0;
+
+		for i in 0..self.vertices.len()
+		{
+
+			let edge = self.vertices[(i + 1) % self.vertices.len()] - self.vertices[i];
+			perimeter += edge.dot(&edge).sqrt();
+
+		}
+
+		return perimeter;
+
+	}
+
+	//The center of mass of the polygon's area. Polygons with no area fall back on the average of their vertices
+	pub fn centroid(&self) -> Point
+	{
+
+		if self.vertices.is_empty()
+		{
+
+			panic!("Cannot find centroid of polygon without vertices.");
+
+		}
+
+		let area = self.signed_area();
+		if area.abs() < FLOATING_POINT_ERROR
+		{
+
+			let sum = self.vertices.iter().fold(Point { x: 0.0, y: 0.0 }, |sum, vertex| sum + *vertex);
+
+			return sum.scale(1.0 / self.vertices.len() as f32);
+
+		}
+
+		let mut centroid = Point { x: 0.0, y: 0.0 };
+
+		for i in 0..self.vertices.len()
+		{
+
+			let current = self.vertices[i];
+			let next = self.vertices[(i + 1) % self.vertices.len()];
+
+			centroid = centroid + (current + next).scale(current.x * next.y - next.x * current.y);
+
+		}
+
+		return centroid.scale(1.0 / (6.0 * area));
+
+	}
+
+	//Traces the outline of a line of sight, i.e. its location followed by the far edge of each triangle in turn, dropping repeated points
+	//A fan which goes all the way around, as from generate_full_line_of_sight, is traced by its far edges alone, as its location is inside it
+	//The result winds counterclockwise, so area, perimeter and centroid can be measured from it directly
+	pub fn from_fan(line_of_sight: &[Triangle]) -> Polygon
+	{
+
+		let mut vertices: Vec<Point> = Vec::new();
+
+		for triangle in line_of_sight.iter()
+		{
+
+			if vertices.is_empty()
+			{
+
+				vertices.push(triangle.vertices[0]);
+
+			}
+
+			for vertex in triangle.vertices[1..].iter()
+			{
+
+				if vertices.last() != Some(vertex)
+				{
+
+					vertices.push(*vertex);
+
+				}
+
+			}
+
+		}
+
+		//The fan closes up if its last ray points the same way as its first, in which case the location is not part of the outline
+		if vertices.len() > 2
+		{
+
+			let location = vertices[0];
+			let first = vertices[1] - location;
+			let last = vertices[vertices.len() - 1] - location;
+
+			if first.dot(&last) > 0.0 && (first.x * last.y - first.y * last.x).abs() < FLOATING_POINT_ERROR
+			{
+
+				vertices.remove(0);
+
+				if (vertices[vertices.len() - 1] - vertices[0]).dot(&(vertices[vertices.len() - 1] - vertices[0])) < FLOATING_POINT_ERROR
+				{
+
+					vertices.pop();
+
+				}
+
+			}
+
+		}
+
+		return Polygon::new(vertices);
+
+	}
+
+	//Splits a simple polygon, convex or not and wound either way, into counterclockwise triangles by clipping ears off it
+	//Collinear vertices are dropped along the way, so the triangles may be fewer than the vertices minus two
+	pub fn triangulate(&self) -> Vec<Triangle>
+	{
+
+		let mut remaining = self.vertices.clone();
+		if self.signed_area() < 0.0
+		{
+
+			remaining.reverse();
+
+		}
+
+		let turn = |a: Point, b: Point, c: Point| (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+
+		let mut triangles: Vec<Triangle> = Vec::new();
+
+		while remaining.len() >= 3
+		{
+
+			let count = remaining.len();
+			let mut clipped = None;
+			let mut flattest = 0;
+
+			for i in 0..count
+			{
+
+				let a = remaining[(i + count - 1) % count];
+				let b = remaining[i];
+				let c = remaining[(i + 1) % count];
+
+				if turn(a, b, c).abs() < turn(remaining[(flattest + count - 1) % count], remaining[flattest], remaining[(flattest + 1) % count]).abs()
+				{
+
+					flattest = i;
+
+				}
+
+				//An ear turns left and has none of the other vertices inside it, so cutting it off leaves a simple polygon behind
+				if turn(a, b, c) > FLOATING_POINT_ERROR
+				{
+
+					let ear = Triangle::new(a, b, c);
+
+					if remaining.iter().all(|&vertex| vertex == a || vertex == b || vertex == c || !ear.contains(vertex))
+					{
+
+						clipped = Some((i, ear));
+						break;
+
+					}
+
+				}
+
+			}
+
+			match clipped
+			{
+
+				Some((i, ear)) =>
+				{
+
+					triangles.push(ear);
+					remaining.remove(i);
+
+				},
+				//Only collinear vertices are left to clip, or the polygon was not simple, so drop the one adding the least area
+				None =>
+				{
+
+					remaining.remove(flattest);
+
+				}
+
+			}
+
+		}
+
+		return triangles;
+
+	}
+
+	//The points of a grid with the given spacing which lie inside the polygon, starting half a step in from the corner of its bounding box
+	pub fn grid(&self, spacing: f32) -> Vec<Point>
+	{
+
+		if spacing <= 0.0
+		{
+
+			panic!("Cannot fill polygon with grid of non-positive spacing.");
+
+		}
+
+		let mut points: Vec<Point> = Vec::new();
+
+		if self.vertices.is_empty()
+		{
+
+			return points;
+
+		}
+
+		let mut min = self.vertices[0];
+		let mut max = self.vertices[0];
+
+		for vertex in self.vertices.iter()
+		{
+
+			min = Point { x: min.x.min(vertex.x), y: min.y.min(vertex.y) };
+			max = Point { x: max.x.max(vertex.x), y: max.y.max(vertex.y) };
+
+		}
+
+		let mut y = min.y + spacing * 0.5;
+		while y < max.y
+		{
+
+			let mut x = min.x + spacing * 0.5;
+			while x < max.x
+			{
+
+				let point = Point { x, y };
+
+				if self.contains(point)
+				{
+
+					points.push(point);
+
+				}
+
+				x += spacing;
+
+			}
+
+			y += spacing;
+
+		}
+
+		return points;
+
+	}
+
+	//Uses the even-odd rule, so points exactly on an edge may fall either way
+	pub fn contains(&self, point: Point) -> bool
+	{
+
+		let mut inside = false;
+
+		for i in 0..self.vertices.len()
+		{
+
+			let a = self.vertices[i];
+			let b = self.vertices[(i + 1) % self.vertices.len()];
+
+			if (a.y > point.y) != (b.y > point.y) && point.x < a.x + (point.y - a.y) * (b.x - a.x) / (b.y - a.y)
+			{
+
+				inside = !inside;
+
+			}
+
+		}
+
+		return inside;
+
+	}
+
+}
+
+//An axis aligned rectangle, used to bound regions which would otherwise extend forever
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rectangle
+{
+
+	pub min: Point,
+	pub max: Point
+
+}
+
+impl Rectangle
+{
+
+	pub fn new(min: Point, max: Point) -> Rectangle
+	{
+
+		if min.x >= max.x || min.y >= max.y
+		{
+
+			panic!("Cannot create rectangle whose minimum is not below and to the left of its maximum.");
+
+		}
+
+		return Rectangle { min, max };
+
+	}
+
+	//The corners in counterclockwise order, starting from min
+	pub fn corners(&self) -> [Point; 4]
+	{
+
+		return [self.min, Point { x: self.max.x, y: self.min.y }, self.max, Point { x: self.min.x, y: self.max.y }];
+
+	}
+
+	//Edge i runs from corners()[i] to corners()[i + 1]
+	pub fn edges(&self) -> [Segment; 4]
+	{
+
+		let corners = self.corners();
+
+		return [
+
+			Segment::new(corners[0], corners[1]),
+			Segment::new(corners[1], corners[2]),
+			Segment::new(corners[2], corners[3]),
+			Segment::new(corners[3], corners[0])
+
+		];
+
+	}
+
+	pub fn contains(&self, point: Point) -> bool
+	{
+
+		return point.x >= self.min.x && point.x <= self.max.x && point.y >= self.min.y && point.y <= self.max.y;
+
+	}
+
+	//Collects the occluders followed by the rectangle's edges, so that line of sight from a location inside the rectangle never escapes to infinity
+	//Triangles whose wall index is at least the number of occluders end on the rectangle rather than on an occluder
+	pub fn close_off<'a, I>(&self, occluders: I) -> Vec<BoundedOccluder<'a, I::Occluder>> where I: OccluderSource<'a>
+	{
+
+		let mut bounded: Vec<BoundedOccluder<'a, I::Occluder>> = occluders.into_occluders().map(BoundedOccluder::Occluder).collect();
+		bounded.extend(self.edges().iter().map(|edge| BoundedOccluder::Bound(*edge)));
+
+		return bounded;
+
+	}
+
+	//Returns where a ray cast from a location inside the rectangle leaves it, along with the index of the edge it leaves through
+	pub fn exit(&self, location: Point, ray: Point) -> (Point, usize)
+	{
+
+		let mut param_x = f32::INFINITY;
+		let mut edge_x = 1;
+
+		if ray.x > 0.0
+		{
+
+			param_x = (self.max.x - location.x) / ray.x;
+
+		}
+		else if ray.x < 0.0
+		{
+
+			param_x = (self.min.x - location.x) / ray.x;
+			edge_x = 3;
+
+		}
+
+		let mut param_y = f32::INFINITY;
+		let mut edge_y = 2;
+
+		if ray.y > 0.0
+		{
+
+			param_y = (self.max.y - location.y) / ray.y;
+
+		}
+		else if ray.y < 0.0
+		{
+
+			param_y = (self.min.y - location.y) / ray.y;
+			edge_y = 0;
+
+		}
+
+		if param_x < param_y
+		{
+
+			return (location + ray.scale(param_x), edge_x);
+
+		}
+
+		return (location + ray.scale(param_y), edge_y);
+
+	}
+
+}
+
 #[cfg(test)]
 mod tests
 {
 
 	use super::*;
+	use crate::sight::generate_full_line_of_sight;
 
     // ANCHOR: sort
 	#[test]
@@ -360,4 +1052,158 @@ mod tests
 	}
     // ANCHOR_END: raycast_test
 
+	#[test]
+	fn parameter()
+	{
+
+		let segment = Segment::new(Point { x: 2.0, y: 1.0 }, Point { x: 6.0, y: 1.0 });
+
+		assert_eq!(0.0, segment.parameter(Point { x: 2.0, y: 1.0 }));
+		assert_eq!(0.25, segment.parameter(Point { x: 3.0, y: 1.0 }));
+		assert_eq!(1.0, segment.parameter(Point { x: 6.0, y: 5.0 }));
+		assert_eq!(-0.5, segment.parameter(Point { x: 0.0, y: -1.0 }));
+
+	}
+
+	#[test]
+	fn triangle_contains()
+	{
+
+		let triangle = Triangle::new(Point { x: 0.0, y: 0.0 }, Point { x: 4.0, y: 0.0 }, Point { x: 0.0, y: 4.0 });
+		let flipped = Triangle::new(Point { x: 0.0, y: 0.0 }, Point { x: 0.0, y: 4.0 }, Point { x: 4.0, y: 0.0 });
+
+		assert!(triangle.contains(Point { x: 1.0, y: 1.0 }));
+		assert!(flipped.contains(Point { x: 1.0, y: 1.0 }));
+		assert!(triangle.contains(Point { x: 2.0, y: 2.0 }));
+		assert!(!triangle.contains(Point { x: 3.0, y: 3.0 }));
+		assert!(!flipped.contains(Point { x: -1.0, y: 1.0 }));
+
+		let collapsed = Triangle::new(Point { x: 1.0, y: 1.0 }, Point { x: 1.0, y: 1.0 }, Point { x: 1.0, y: 1.0 });
+		let flat = Triangle::new(Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 0.0 }, Point { x: 2.0, y: 0.0 });
+
+		assert!(!collapsed.contains(Point { x: 1.0, y: 1.0 }));
+		assert!(!flat.contains(Point { x: 0.5, y: 0.0 }));
+		assert!(!flat.contains(Point { x: 5.0, y: 0.0 }));
+
+	}
+
+	#[test]
+	fn polygon_area()
+	{
+
+		let square = Polygon::new(vec![Point { x: 0.0, y: 0.0 }, Point { x: 2.0, y: 0.0 }, Point { x: 2.0, y: 2.0 }, Point { x: 0.0, y: 2.0 }]);
+		let notch = Polygon::new(vec![Point { x: 0.0, y: 0.0 }, Point { x: 0.0, y: 2.0 }, Point { x: 2.0, y: 2.0 }, Point { x: 1.0, y: 1.0 }, Point { x: 2.0, y: 0.0 }]);
+
+		assert_eq!(4.0, square.signed_area());
+		assert_eq!(-3.0, notch.signed_area());
+		assert_eq!(3.0, notch.area());
+
+	}
+
+	#[test]
+	fn polygon_triangulate()
+	{
+
+		let notch = Polygon::new(vec![Point { x: 0.0, y: 0.0 }, Point { x: 0.0, y: 2.0 }, Point { x: 2.0, y: 2.0 }, Point { x: 1.0, y: 1.0 }, Point { x: 2.0, y: 0.0 }]);
+		let triangles = notch.triangulate();
+
+		assert_eq!(3, triangles.len());
+		assert!((triangles.iter().map(|triangle| triangle.area()).sum::<f32>() - 3.0).abs() < FLOATING_POINT_ERROR);
+		assert!(triangles.iter().all(|triangle| Polygon::new(triangle.vertices.to_vec()).signed_area() > 0.0));
+		assert!(triangles.iter().all(|triangle| !triangle.contains(Point { x: 1.5, y: 1.0 })));
+
+		//Collinear vertices along an edge add nothing
+		let square = Polygon::new(vec![Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 0.0 }, Point { x: 2.0, y: 0.0 }, Point { x: 2.0, y: 2.0 }, Point { x: 0.0, y: 2.0 }]);
+		assert!((square.triangulate().iter().map(|triangle| triangle.area()).sum::<f32>() - 4.0).abs() < FLOATING_POINT_ERROR);
+
+	}
+
+	#[test]
+	fn polygon_contains()
+	{
+
+		let notch = Polygon::new(vec![Point { x: 0.0, y: 0.0 }, Point { x: 0.0, y: 2.0 }, Point { x: 2.0, y: 2.0 }, Point { x: 1.0, y: 1.0 }, Point { x: 2.0, y: 0.0 }]);
+
+		assert!(notch.contains(Point { x: 0.5, y: 1.0 }));
+		assert!(notch.contains(Point { x: 1.5, y: 1.8 }));
+		assert!(!notch.contains(Point { x: 1.5, y: 1.0 }));
+		assert!(!notch.contains(Point { x: -1.0, y: 1.0 }));
+
+	}
+
+	#[test]
+	fn metrics()
+	{
+
+		let triangle = Triangle::new(Point { x: 0.0, y: 0.0 }, Point { x: 3.0, y: 0.0 }, Point { x: 0.0, y: 3.0 });
+		assert_eq!(4.5, triangle.area());
+		assert_eq!(Point { x: 1.0, y: 1.0 }, triangle.centroid());
+
+		let notch = Polygon::new(vec![Point { x: 0.0, y: 0.0 }, Point { x: 2.0, y: 0.0 }, Point { x: 2.0, y: 2.0 }, Point { x: 0.0, y: 2.0 }, Point { x: 0.0, y: 1.0 }]);
+		assert_eq!(8.0, notch.perimeter());
+		assert_eq!(Point { x: 1.0, y: 1.0 }, notch.centroid());
+
+		//A fan whose middle triangle has collapsed to a line, as when rays graze a wall's endpoint
+		let location = Point { x: 0.0, y: 0.0 };
+		let fan = [
+			Triangle::new(location, Point { x: 2.0, y: -2.0 }, Point { x: 2.0, y: 0.0 }),
+			Triangle::new(location, Point { x: 2.0, y: 0.0 }, Point { x: 2.0, y: 0.0 }),
+			Triangle::new(location, Point { x: 2.0, y: 0.0 }, Point { x: 2.0, y: 2.0 })
+		];
+
+		let outline = Polygon::from_fan(&fan);
+		assert_eq!(vec![location, Point { x: 2.0, y: -2.0 }, Point { x: 2.0, y: 0.0 }, Point { x: 2.0, y: 2.0 }], outline.vertices);
+		assert_eq!(4.0, outline.area());
+		assert!((outline.perimeter() - (4.0 + 2.0 * 8.0_f32.sqrt())).abs() < FLOATING_POINT_ERROR);
+		assert!((outline.centroid() - Point { x: 4.0 / 3.0, y: 0.0 }).dot(&(outline.centroid() - Point { x: 4.0 / 3.0, y: 0.0 })) < FLOATING_POINT_ERROR);
+
+		//Looking all around from the middle of a room traces just the walls, without cutting in to the location and back
+		let room = Rectangle::new(Point { x: 0.0, y: 0.0 }, Point { x: 10.0, y: 10.0 });
+		let outline = Polygon::from_fan(&generate_full_line_of_sight(Point { x: 5.0, y: 5.0 }, &room.edges(), ALL_LAYERS));
+
+		assert!(!outline.vertices.contains(&Point { x: 5.0, y: 5.0 }));
+		assert!((outline.perimeter() - 40.0).abs() < FLOATING_POINT_ERROR);
+		assert!((outline.area() - 100.0).abs() < FLOATING_POINT_ERROR);
+		assert!((outline.centroid() - Point { x: 5.0, y: 5.0 }).dot(&(outline.centroid() - Point { x: 5.0, y: 5.0 })) < FLOATING_POINT_ERROR);
+
+	}
+
+	#[test]
+	fn rectangle_exit()
+	{
+
+		let bounds = Rectangle::new(Point { x: 0.0, y: 0.0 }, Point { x: 10.0, y: 4.0 });
+		let location = Point { x: 2.0, y: 1.0 };
+
+		assert_eq!((Point { x: 10.0, y: 1.0 }, 1), bounds.exit(location, Point { x: 2.0, y: 0.0 }));
+		assert_eq!((Point { x: 5.0, y: 4.0 }, 2), bounds.exit(location, Point { x: 1.0, y: 1.0 }));
+		assert_eq!((Point { x: 0.0, y: 0.0 }, 0), bounds.exit(location, Point { x: -2.0, y: -1.0 }));
+		assert_eq!((Point { x: 0.0, y: 2.0 }, 3), bounds.exit(location, Point { x: -2.0, y: 1.0 }));
+
+	}
+
+	#[test]
+	fn occludes()
+	{
+
+		let wall = Segment::new(Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 0.0 });
+		let fence = Segment::with_layers(Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 0.0 }, 0b01);
+		let smoke = Segment::with_layers(Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 0.0 }, 0b10);
+
+		assert!(wall.occludes(0b01));
+		assert!(wall.occludes(0b10));
+
+		assert!(fence.occludes(0b01));
+		assert!(!fence.occludes(0b10));
+
+		assert!(!smoke.occludes(0b01));
+		assert!(smoke.occludes(0b11));
+
+		let mut door = Segment::new(Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 0.0 });
+		door.enabled = false;
+
+		assert!(!door.occludes(ALL_LAYERS));
+
+	}
+
 }
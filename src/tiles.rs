@@ -0,0 +1,189 @@
+use super::space::{Point, Segment};
+
+//Tiles outside the map are empty
+fn is_solid(solid: &[bool], width: usize, height: usize, column: isize, row: isize) -> bool
+{
+
+	if column < 0 || row < 0 || column as usize >= width || row as usize >= height
+	{
+
+		return false;
+
+	}
+
+	return solid[row as usize * width + column as usize];
+
+}
+
+//Solid tiles are given row major, i.e. tile (column, row) is solid[row * width + column], and cover the square from
+//origin + (column, row) * tile_size to origin + (column + 1, row + 1) * tile_size
+//Traces the boundary of the solid tiles into horizontal and vertical segments, joining runs of edges which face the same way
+//Segments wind counterclockwise around solid regions, and edges shared by two solid tiles are dropped
+pub fn tilemap_to_segments(solid: &[bool], width: usize, height: usize, origin: Point, tile_size: f32) -> Vec<Segment>
+{
+
+	if solid.len() != width * height
+	{
+
+		panic!("Cannot trace tilemap whose tile count does not match its dimensions.");
+
+	}
+
+	let corner = |column: usize, row: usize| Point { x: origin.x + column as f32 * tile_size, y: origin.y + row as f32 * tile_size };
+
+	let mut segments: Vec<Segment> = Vec::new();
+
+	//Horizontal edges lie between row - 1 and row. The run tracks where it started and whether the solid tile is below it
+	for row in 0..height + 1
+	{
+
+		let mut run: Option<(usize, bool)> = None;
+
+		for column in 0..width + 1
+		{
+
+			let below = is_solid(solid, width, height, column as isize, row as isize - 1);
+			let above = is_solid(solid, width, height, column as isize, row as isize);
+			let current = if column < width && below != above { Some(below) } else { None };
+
+			if let Some((start, solid_below)) = run
+			{
+
+				if current == Some(solid_below)
+				{
+
+					continue;
+
+				}
+
+				if solid_below
+				{
+
+					segments.push(Segment::new(corner(column, row), corner(start, row)));
+
+				}
+				else
+				{
+
+					segments.push(Segment::new(corner(start, row), corner(column, row)));
+
+				}
+
+			}
+
+			run = current.map(|solid_below| (column, solid_below));
+
+		}
+
+	}
+
+	//Vertical edges lie between column - 1 and column. The run tracks where it started and whether the solid tile is to its left
+	for column in 0..width + 1
+	{
+
+		let mut run: Option<(usize, bool)> = None;
+
+		for row in 0..height + 1
+		{
+
+			let left = is_solid(solid, width, height, column as isize - 1, row as isize);
+			let right = is_solid(solid, width, height, column as isize, row as isize);
+			let current = if row < height && left != right { Some(left) } else { None };
+
+			if let Some((start, solid_left)) = run
+			{
+
+				if current == Some(solid_left)
+				{
+
+					continue;
+
+				}
+
+				if solid_left
+				{
+
+					segments.push(Segment::new(corner(column, start), corner(column, row)));
+
+				}
+				else
+				{
+
+					segments.push(Segment::new(corner(column, row), corner(column, start)));
+
+				}
+
+			}
+
+			run = current.map(|solid_left| (row, solid_left));
+
+		}
+
+	}
+
+	return segments;
+
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	fn parse(rows: &[&str]) -> Vec<bool>
+	{
+
+		return rows.iter().flat_map(|row| row.chars().map(|tile| tile == '#')).collect();
+
+	}
+
+	#[test]
+	fn single_tile()
+	{
+
+		let segments = tilemap_to_segments(&[true], 1, 1, Point { x: 0.0, y: 0.0 }, 2.0);
+
+		assert_eq!(4, segments.len());
+		assert!(segments.contains(&Segment::new(Point { x: 0.0, y: 0.0 }, Point { x: 2.0, y: 0.0 })));
+		assert!(segments.contains(&Segment::new(Point { x: 2.0, y: 0.0 }, Point { x: 2.0, y: 2.0 })));
+		assert!(segments.contains(&Segment::new(Point { x: 2.0, y: 2.0 }, Point { x: 0.0, y: 2.0 })));
+		assert!(segments.contains(&Segment::new(Point { x: 0.0, y: 2.0 }, Point { x: 0.0, y: 0.0 })));
+
+	}
+
+	#[test]
+	fn merged_runs()
+	{
+
+		//A solid block only needs its four outer edges, and an L shape needs six
+		let block = parse(&["###", "###"]);
+		assert_eq!(4, tilemap_to_segments(&block, 3, 2, Point { x: 0.0, y: 0.0 }, 1.0).len());
+
+		let l_shape = parse(&["#..", "###"]);
+		let segments = tilemap_to_segments(&l_shape, 3, 2, Point { x: 0.0, y: 0.0 }, 1.0);
+
+		assert_eq!(6, segments.len());
+		assert!(segments.contains(&Segment::new(Point { x: 3.0, y: 2.0 }, Point { x: 0.0, y: 2.0 })));
+
+	}
+
+	#[test]
+	fn touching_corners()
+	{
+
+		//Tiles meeting only at a corner keep separate outlines
+		let diagonal = parse(&["#.", ".#"]);
+		assert_eq!(8, tilemap_to_segments(&diagonal, 2, 2, Point { x: 0.0, y: 0.0 }, 1.0).len());
+
+		//A room with a hole in the middle has an outer and an inner outline
+		let room = parse(&["###", "#.#", "###"]);
+		let segments = tilemap_to_segments(&room, 3, 3, Point { x: 0.0, y: 0.0 }, 1.0);
+
+		assert_eq!(8, segments.len());
+		assert!(segments.contains(&Segment::new(Point { x: 1.0, y: 1.0 }, Point { x: 1.0, y: 2.0 })));
+		assert!(segments.contains(&Segment::new(Point { x: 2.0, y: 1.0 }, Point { x: 1.0, y: 1.0 })));
+
+	}
+
+}